@@ -11,12 +11,11 @@ fn main() {
     let exam_tester = ExamTester::new(exam_info);
 
     // Copying student submissions and tests to the grading directory.
-    // Note the ordering of the operations:
-    // As no files are overwritten, copying the submissions first
-    // copies the students' tests into the grading directory
-    // and adds only the extra tests from the tasks directory.
-    // Otherwise, the original tests would be prioritized.
-    exam_tester.copy_submissions();
-    exam_tester.copy_tests();
-    exam_tester.run_tests();
+    // Whether the students' tests or the original tests are kept doesn't depend
+    // on the ordering of the operations, but on the file precedence of the grading options.
+    // By default, the students' tests are kept and only the extra tests are added.
+    let mut problems = exam_tester.copy_submissions().unwrap();
+    problems.extend(exam_tester.copy_tests().unwrap());
+    problems.extend(exam_tester.run_tests().unwrap());
+    print!("{}", problems.to_text());
 }
@@ -1,34 +1,322 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::time::Duration;
 
-use src2exam::exam_tester::exam::{ExamInfo, ExamTester};
+use src2exam::exam_tester::exam::{
+    AssignmentBuilder, ExamInfo, ExamTester, FilePrecedence, ProblemReport, TestIsolation,
+    CONFIG_FILE,
+};
+use src2exam::exam_tester::process::Sandbox;
+
+/// Exit code if grading finished, but problems need manual attention.
+const EXIT_PROBLEMS: i32 = 2;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     /// The directory to use as the base directory for the exam.
-    #[arg(short, long, default_value = ExamInfo::base_dir_default().into_os_string())]
+    #[arg(short, long, global = true, default_value = ExamInfo::base_dir_default().into_os_string())]
     directory: PathBuf,
     /// The timeout for running the tests in seconds.
-    #[arg(short, long, default_value = ExamInfo::test_timeout_default().as_secs().to_string())]
-    timeout: u64,
+    /// Overrides the timeout of the exam configuration (default: 30).
+    #[arg(short, long, global = true)]
+    timeout: Option<f64>,
+    /// The number of test runs to execute at the same time.
+    #[arg(short, long, global = true, default_value_t = 1)]
+    jobs: usize,
+    /// Runs the tests of all Go tasks with a single `go test ./...` in the grading directory.
+    /// Requires the grading directory to be part of a Go module.
+    #[arg(long, global = true)]
+    go_batch: bool,
+    /// The maximum time of the single `go test ./...` with `--go-batch` in seconds.
+    /// If it takes longer, the Go tasks are run separately.
+    /// Default: the timeout for building and for each round of packages tested in parallel.
+    #[arg(long, global = true)]
+    go_batch_timeout: Option<f64>,
+    /// Runs the tests in a sandbox without network access, using bubblewrap (`bwrap`).
+    /// Uses the sandbox settings of the exam configuration, if any.
+    #[arg(long, global = true)]
+    sandbox: bool,
+    /// How the tests handed in by the students are handled in the test runs
+    /// (keep, exclude, quarantine or rename).
+    /// Overrides the test isolation of the exam configuration (default: keep).
+    #[arg(long, global = true)]
+    test_isolation: Option<TestIsolation>,
+    /// Which version of a file is kept if a submission and a task contain different versions
+    /// (submission, grading or error).
+    /// Overrides the file precedence of the exam configuration (default: submission).
+    #[arg(long, global = true)]
+    file_precedence: Option<FilePrecedence>,
+    /// Runs the tests on the submissions without verifying them against the reference solutions first.
+    #[arg(long, global = true)]
+    no_verify: bool,
+    /// Writes the problems that need manual attention (e.g. submissions that cannot be read
+    /// or test runs that cannot be started) to the given file, in addition to printing them.
+    #[arg(long, global = true)]
+    problems: Option<PathBuf>,
+    /// The step to run. Runs `collect`, `prepare` and `test` if not given.
+    /// The tests are verified against the reference solutions before testing the submissions.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Creates the exam directories and a configuration file with the default settings.
+    Init,
+    /// Creates the assignment handed out to the students from the tasks:
+    /// Removes the solution blocks and leaves out the grading tests.
+    Assignment {
+        /// The directory to create the assignment in, which must not exist yet.
+        /// Default: `assignment` in the base directory.
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Code replacing each solution block, e.g. `panic("TODO")`.
+        #[arg(long)]
+        placeholder: Option<String>,
+        /// Doesn't pack the assignment into a zip file next to the assignment directory.
+        #[arg(long)]
+        no_zip: bool,
+    },
+    /// Checks that the assignment templates of all tasks build and fail the grading tests.
+    CheckTemplates {
+        /// Code replacing each solution block, e.g. `panic("TODO")`.
+        #[arg(long)]
+        placeholder: Option<String>,
+    },
+    /// Copies the submissions into the grading directory.
+    Collect,
+    /// Copies the tests from the tasks to the submissions in the grading directory.
+    Prepare,
+    /// Runs the tests against the reference solutions in the task directories
+    /// and reports failing, flaky and slow tests.
+    Verify,
+    /// Runs the tests in the grading directory and saves the results.
+    /// Verifies the tests against the reference solutions first, unless `--no-verify` is given.
+    Test,
+    /// Shows the saved results without running any tests.
+    Report {
+        /// Writes the results as CSV to the given file instead of printing a table.
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Shows which students and tasks were already tested.
+    Status,
+    /// Removes the saved results.
+    Clean {
+        /// Removes the whole grading directory, including all annotations.
+        /// Asks for confirmation unless `--yes` is given.
+        #[arg(long)]
+        all: bool,
+        /// Removes the grading directory with `--all` without asking for confirmation.
+        #[arg(long, requires = "all")]
+        yes: bool,
+    },
 }
 
 fn main() {
     let args = Args::parse();
     let base_dir = args.directory;
     let base_dir = if base_dir.is_relative() {
-        std::env::current_dir().unwrap().join(base_dir)
+        ExamInfo::base_dir_default().join(base_dir)
     } else {
         base_dir
     };
 
     println!("Using base directory: {:?}", base_dir);
 
-    let exam_info = ExamInfo::new(base_dir);
-    let exam_tester = ExamTester::new(exam_info);
+    let mut exam_info = if base_dir.join(CONFIG_FILE).exists() {
+        println!("Using configuration: {:?}", base_dir.join(CONFIG_FILE));
+        exit_on_error(ExamInfo::from_config_file(&base_dir))
+    } else {
+        ExamInfo::new(base_dir)
+    };
+    if let Some(timeout) = args.timeout {
+        let timeout = Duration::try_from_secs_f64(timeout)
+            .map_err(|e| format!("invalid timeout {}: {}", timeout, e));
+        exam_info.set_test_timeout(exit_on_error(timeout));
+    }
+    let mut options = *exam_info.grading_options();
+    if let Some(test_isolation) = args.test_isolation {
+        options.test_isolation = test_isolation;
+    }
+    if let Some(file_precedence) = args.file_precedence {
+        options.file_precedence = file_precedence;
+    }
+    exam_info.set_grading_options(options);
+    if args.sandbox && exam_info.sandbox().is_none() {
+        exam_info.set_sandbox(Sandbox::default());
+    }
+    let go_batch_timeout = args.go_batch_timeout.map(|timeout| {
+        exit_on_error(
+            Duration::try_from_secs_f64(timeout)
+                .map_err(|e| format!("invalid go batch timeout {}: {}", timeout, e)),
+        )
+    });
+    let exam_tester = ExamTester::new(exam_info)
+        .with_jobs(args.jobs)
+        .with_go_batch(args.go_batch)
+        .with_go_batch_timeout(go_batch_timeout)
+        .with_verification(!args.no_verify);
+    let messages = exam_tester.exam_info().grading_options().messages;
+
+    let mut problems = ProblemReport::new();
+    match args.command {
+        None => {
+            problems.extend(exit_on_error(exam_tester.copy_submissions()));
+            problems.extend(exit_on_error(exam_tester.copy_tests()));
+            problems.extend(exit_on_error(exam_tester.run_tests()));
+        }
+        Some(Command::Init) => {
+            exit_on_error(exam_tester.exam_info().init());
+            println!(
+                "Initialized exam in {:?}",
+                exam_tester.exam_info().base_dir()
+            );
+        }
+        Some(Command::Assignment {
+            output,
+            placeholder,
+            no_zip,
+        }) => {
+            let dir =
+                output.unwrap_or_else(|| exam_tester.exam_info().base_dir().join("assignment"));
+            let builder = AssignmentBuilder::new(exam_tester.exam_info().clone())
+                .with_placeholder(placeholder);
+            if no_zip {
+                exit_on_error(builder.build(&dir));
+            } else {
+                let zip_file = dir.with_extension("zip");
+                exit_on_error(builder.build_zip(&dir, &zip_file));
+                println!("Assignment packed into {:?}", zip_file);
+            }
+            println!("Assignment created in {:?}", dir);
+        }
+        Some(Command::CheckTemplates { placeholder }) => {
+            let builder = AssignmentBuilder::new(exam_tester.exam_info().clone())
+                .with_placeholder(placeholder);
+            let checks = exit_on_error(builder.check_templates());
+            let failed: Vec<&str> = checks
+                .iter()
+                .filter(|check| !check.passed())
+                .map(|check| check.task.as_str())
+                .collect();
+            if !failed.is_empty() {
+                eprintln!("Templates with problems: {}", failed.join(", "));
+                std::process::exit(1);
+            }
+            println!("All templates build and fail the grading tests");
+        }
+        Some(Command::Collect) => problems.extend(exit_on_error(exam_tester.copy_submissions())),
+        Some(Command::Prepare) => problems.extend(exit_on_error(exam_tester.copy_tests())),
+        Some(Command::Verify) => {
+            let report = exit_on_error(exam_tester.verify());
+            if !report.passed() {
+                eprint!("{}", report.to_text());
+                std::process::exit(1);
+            }
+            print!("{}", report.to_text());
+            println!("The tests of all tasks pass with the reference solutions");
+        }
+        Some(Command::Test) => problems.extend(exit_on_error(exam_tester.run_tests())),
+        Some(Command::Report { csv }) => {
+            let table = exit_on_error(exam_tester.result_table());
+            match csv {
+                Some(file) => {
+                    exit_on_error(std::fs::write(&file, table.to_csv(messages)));
+                    println!("Results written to {:?}", file);
+                }
+                None => print!("{}", table.to_text(messages)),
+            }
+        }
+        Some(Command::Status) => {
+            let table = exit_on_error(exam_tester.result_table());
+            for (student_name, results) in &table.rows {
+                let untested: Vec<&str> = table
+                    .tasks
+                    .iter()
+                    .zip(results)
+                    .filter(|(_, result)| result.is_none())
+                    .map(|(task_name, _)| task_name.as_str())
+                    .collect();
+                if untested.is_empty() {
+                    println!("  {}: all tasks tested", student_name);
+                } else {
+                    println!("  {}: not tested: {}", student_name, untested.join(", "));
+                }
+            }
+            let (tested, total) = table.tested_count();
+            println!("{} of {} tests run", tested, total);
+        }
+        Some(Command::Clean { all, yes }) => {
+            let exam_info = exam_tester.exam_info();
+            if all {
+                let grading_dir = exam_info.grading_dir();
+                if grading_dir.exists() && !yes && !confirm_removal(&grading_dir) {
+                    eprintln!("Not removing {:?}", grading_dir);
+                    std::process::exit(1);
+                }
+                if grading_dir.exists() {
+                    exit_on_error(std::fs::remove_dir_all(&grading_dir));
+                }
+                println!("Removed {:?}", grading_dir);
+            } else {
+                let result_store = exam_info.result_store();
+                exit_on_error(result_store.clear());
+                println!("Removed {:?}", result_store.dir());
+            }
+        }
+    }
+    report_problems(&problems, args.problems.as_deref());
+}
+
+/// Prints the given problems and writes them to the given file, if any.
+/// Exits with [`EXIT_PROBLEMS`] if there are problems.
+fn report_problems(problems: &ProblemReport, file: Option<&std::path::Path>) {
+    if let Some(file) = file {
+        exit_on_error(std::fs::write(file, problems.to_text()));
+    }
+    if problems.is_empty() {
+        return;
+    }
+    eprintln!(
+        "{} problem(s) need manual attention:",
+        problems.problems().len()
+    );
+    for problem in problems.problems() {
+        eprintln!("  {}", problem);
+    }
+    if let Some(file) = file {
+        eprintln!("Problems written to {:?}", file);
+    }
+    std::process::exit(EXIT_PROBLEMS);
+}
+
+/// Asks on the terminal whether the given directory should be removed.
+/// Returns false without asking if stdin is not a terminal.
+fn confirm_removal(dir: &std::path::Path) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        eprintln!("Use --yes to remove the grading directory without confirmation");
+        return false;
+    }
+    print!(
+        "Remove {:?} including all annotations and results? [y/N] ",
+        dir
+    );
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
 
-    exam_tester.copy_submissions();
-    exam_tester.copy_tests();
-    exam_tester.run_tests();
+/// Returns the value of the given result or prints the error and exits.
+fn exit_on_error<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    })
 }
@@ -0,0 +1,165 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Errors of the library.
+///
+/// Each error carries the context needed to report it, e.g. the path of the file
+/// and the operation that failed, or the student and task of a test run.
+/// Failures of submissions (e.g. build failures) are not errors,
+/// they are regular test results.
+#[derive(Debug)]
+pub enum Error {
+    /// An operation on a file or directory failed.
+    Io {
+        /// The operation that failed, e.g. `read directory`.
+        operation: &'static str,
+        /// The file or directory the operation failed on.
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A configuration file is invalid.
+    Config {
+        /// The configuration file, `None` if the configuration wasn't read from a file.
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// A report (e.g. a JUnit XML report or a saved test report) could not be parsed.
+    Parse {
+        /// The format of the report, e.g. `JUnit XML`.
+        format: &'static str,
+        /// The file the report was read from, if any.
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// The language of a task is neither configured nor detected.
+    UnknownLanguage { task: String },
+    /// A submission and a task contain different versions of a file
+    /// and the file precedence doesn't allow to choose one.
+    FileConflict { path: PathBuf },
+    /// The tests of the given tasks don't pass reliably with the reference solutions.
+    Verification { tasks: Vec<String> },
+    /// The sandbox for test runs cannot be used.
+    Sandbox { message: String },
+    /// The test runner failed, e.g. because the toolchain could not be started.
+    TestRun { source: io::Error },
+    /// An error while handling the submission of a student.
+    Student { student: String, source: Box<Error> },
+    /// An error while handling a task of a student.
+    Task {
+        student: String,
+        task: String,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Creates an error for the given failed operation on the given path.
+    pub fn io<P: AsRef<Path>>(operation: &'static str, path: P, source: io::Error) -> Self {
+        Error::Io {
+            operation,
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    /// Adds the student to the error.
+    pub fn in_student<S: Into<String>>(self, student: S) -> Self {
+        Error::Student {
+            student: student.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Adds the student and task to the error.
+    pub fn in_task<S: Into<String>, T: Into<String>>(self, student: S, task: T) -> Self {
+        Error::Task {
+            student: student.into(),
+            task: task.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io {
+                operation,
+                path,
+                source,
+            } => write!(f, "failed to {} {}: {}", operation, path.display(), source),
+            Error::Config {
+                path: Some(path),
+                message,
+            } => write!(f, "{}: {}", path.display(), message),
+            Error::Config {
+                path: None,
+                message,
+            } => write!(f, "invalid configuration: {}", message),
+            Error::Parse {
+                format,
+                path,
+                message,
+            } => {
+                write!(f, "invalid {}", format)?;
+                if let Some(path) = path {
+                    write!(f, " in {}", path.display())?;
+                }
+                write!(f, ": {}", message)
+            }
+            Error::UnknownLanguage { task } => {
+                write!(f, "unknown language of task {}", task)
+            }
+            Error::FileConflict { path } => write!(
+                f,
+                "different versions of {} in the submission and the task",
+                path.display()
+            ),
+            Error::Verification { tasks } => write!(
+                f,
+                "the tests of {} don't pass reliably with the reference solution",
+                tasks.join(", ")
+            ),
+            Error::Sandbox { message } => write!(f, "{}", message),
+            Error::TestRun { source } => write!(f, "failed to run tests: {}", source),
+            Error::Student { student, source } => write!(f, "{}: {}", student, source),
+            Error::Task {
+                student,
+                task,
+                source,
+            } => write!(f, "{}/{}: {}", student, task, source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } | Error::TestRun { source } => Some(source),
+            Error::Student { source, .. } | Error::Task { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_with_context() {
+        let error = Error::io(
+            "read directory",
+            "abgaben",
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        )
+        .in_task("student_1", "task_1");
+
+        assert_eq!(
+            error.to_string(),
+            "student_1/task_1: failed to read directory abgaben: permission denied"
+        );
+        assert!(std::error::Error::source(&error).is_some());
+    }
+}
@@ -0,0 +1,407 @@
+use crate::exam_tester::exam::{
+    ExamInfo, TaskInfo, TestIsolation, TestReport, TestResult, TASK_CONFIG_FILE,
+};
+use crate::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Marker of the first line of a solution block, after the line comment prefix.
+pub const BEGIN_SOLUTION: &str = "begin:solution";
+/// Marker of the last line of a solution block, after the line comment prefix.
+pub const END_SOLUTION: &str = "end:solution";
+
+/// Creates the assignment handed out to the students from the tasks of an exam,
+/// so the assignment and the grading are based on the same files.
+///
+/// For each task, the files of the task directory are copied to `<dir>/<task>`, except for
+/// * the grading tests (see [`TaskInfo::grading_tests`], `*_grading_test.go` for Go by default),
+/// * the task configuration file ([`TASK_CONFIG_FILE`]).
+///
+/// Solution blocks, i.e. the lines from a `//begin:solution` to an `//end:solution`
+/// comment (or `#begin:solution` and `#end:solution`), are removed from the files
+/// and optionally replaced by a placeholder, e.g. `panic("TODO")`.
+/// The module files of the tasks (e.g. the `go.mod` of the exam) are copied to the
+/// assignment directory itself.
+pub struct AssignmentBuilder {
+    exam_info: ExamInfo,
+    placeholder: Option<String>,
+}
+
+impl AssignmentBuilder {
+    pub fn new(exam_info: ExamInfo) -> Self {
+        Self {
+            exam_info,
+            placeholder: None,
+        }
+    }
+
+    /// Sets the placeholder replacing the solution blocks, indented like the begin marker.
+    /// Without placeholder, the solution blocks are removed.
+    pub fn with_placeholder(mut self, placeholder: Option<String>) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Creates the assignment in the given directory, which must not exist yet.
+    pub fn build(&self, dir: &Path) -> Result<(), Error> {
+        if dir.exists() {
+            return Err(Error::io(
+                "create directory",
+                dir,
+                io::Error::new(io::ErrorKind::AlreadyExists, "already exists"),
+            ));
+        }
+        for task in self.exam_info.tasks()? {
+            let task_dest = dir.join(&task.name);
+            fs::create_dir_all(&task_dest)
+                .map_err(|e| Error::io("create directory", &task_dest, e))?;
+            let excluded = excluded_files(&task)?;
+            for file in crate::filesystem::files_with_suffix_recursive(&task.dir, "")? {
+                if !excluded.contains(&file) {
+                    self.copy_file(&task.dir.join(&file), &task_dest.join(&file))?;
+                }
+            }
+            if let Some(language) = &task.language {
+                for file in language.module_files(&task.dir) {
+                    let Some(file_name) = file.file_name() else {
+                        continue;
+                    };
+                    let dest = dir.join(file_name);
+                    if !dest.exists() {
+                        fs::copy(&file, &dest).map_err(|e| Error::io("copy", &file, e))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the assignment in the given directory (see [`AssignmentBuilder::build`])
+    /// and packs it into the given zip file. The files are stored in the zip file
+    /// below a directory named like the assignment directory.
+    pub fn build_zip(&self, dir: &Path, zip_file: &Path) -> Result<(), Error> {
+        self.build(dir)?;
+        let root = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "assignment".to_string());
+        let file = fs::File::create(zip_file).map_err(|e| Error::io("create", zip_file, e))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+        let zip_error =
+            |e: zip::result::ZipError| Error::io("write", zip_file, io::Error::other(e));
+        for file in crate::filesystem::files_with_suffix_recursive(dir, "")? {
+            let path = dir.join(&file);
+            let content = fs::read(&path).map_err(|e| Error::io("read", &path, e))?;
+            zip.start_file(format!("{}/{}", root, file), options)
+                .map_err(zip_error)?;
+            zip.write_all(&content)
+                .map_err(|e| Error::io("write", zip_file, e))?;
+        }
+        zip.finish().map_err(zip_error)?;
+        Ok(())
+    }
+
+    /// Checks the templates of all tasks, i.e. the files of the assignment:
+    /// Creates the assignment in a temporary directory,
+    /// adds the tests deciding the grading to each task (see [`TestIsolation`])
+    /// and runs them with the runner of the task's language.
+    ///
+    /// The templates are expected to build and to fail the tests,
+    /// see [`TemplateCheck::passed`]. A message is printed for each task.
+    pub fn check_templates(&self) -> Result<Vec<TemplateCheck>, Error> {
+        let temp_dir = tempfile::tempdir()
+            .map_err(|e| Error::io("create directory in", std::env::temp_dir(), e))?;
+        let dir = temp_dir.path().join("assignment");
+        self.build(&dir)?;
+
+        let mut checks = Vec::new();
+        for task in self.exam_info.tasks()? {
+            println!("Checking template of task: {}", task.name);
+            let check = TemplateCheck {
+                report: check_template(&task, &dir.join(&task.name)),
+                task: task.name,
+            };
+            println!("  {}", check);
+            checks.push(check);
+        }
+        Ok(checks)
+    }
+
+    /// Copies a file of a task, removing the solution blocks from text files.
+    fn copy_file(&self, src: &Path, dest: &Path) -> Result<(), Error> {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::io("create directory", parent, e))?;
+        }
+        let content = fs::read(src).map_err(|e| Error::io("read", src, e))?;
+        let content = match String::from_utf8(content) {
+            Ok(text) => strip_solutions(&text, self.placeholder.as_deref())
+                .map_err(|message| Error::Parse {
+                    format: "solution markers",
+                    path: Some(src.to_path_buf()),
+                    message,
+                })?
+                .into_bytes(),
+            Err(e) => e.into_bytes(),
+        };
+        fs::write(dest, content).map_err(|e| Error::io("write", dest, e))
+    }
+}
+
+/// The result of checking the template of a task, see [`AssignmentBuilder::check_templates`].
+#[derive(Debug)]
+pub struct TemplateCheck {
+    /// The name of the task.
+    pub task: String,
+    /// The report of running the tests against the template,
+    /// or the error if the tests could not be run.
+    pub report: Result<TestReport, Error>,
+}
+
+impl TemplateCheck {
+    /// Returns whether the template builds and the tests fail, i.e. whether the result
+    /// is a test failure or a crash (e.g. caused by a placeholder like `panic("TODO")`).
+    /// A template passing the tests means that the tests or the solution markers are broken.
+    pub fn passed(&self) -> bool {
+        matches!(
+            self.report.as_ref().map(|report| report.result()),
+            Ok(TestResult::TestFailure | TestResult::Crash)
+        )
+    }
+}
+
+impl fmt::Display for TemplateCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = match &self.report {
+            Ok(report) => report,
+            Err(e) => return write!(f, "{}: tests could not be run: {}", self.task, e),
+        };
+        match report.result() {
+            TestResult::TestFailure | TestResult::Crash => write!(f, "{}: ok", self.task),
+            TestResult::Success => write!(
+                f,
+                "{}: the tests pass with the template, i.e. they don't test the solution",
+                self.task
+            ),
+            TestResult::BuildFailure => {
+                write!(f, "{}: the template doesn't build", self.task)?;
+                for line in report.output.lines() {
+                    write!(f, "\n    {}", line)?;
+                }
+                Ok(())
+            }
+            result => write!(
+                f,
+                "{}: unexpected result with the template: {}",
+                self.task,
+                result.to_string_en()
+            ),
+        }
+    }
+}
+
+/// Runs the tests deciding the grading against the template of the given task in the given directory.
+fn check_template(task: &TaskInfo, dir: &Path) -> Result<TestReport, Error> {
+    let Some(language) = &task.language else {
+        return Err(Error::UnknownLanguage {
+            task: task.name.clone(),
+        });
+    };
+    TestIsolation::Exclude.apply(task, dir)?;
+    let mut report = language
+        .runner(dir, task.timeout, task.limits, task.sandbox.as_ref())
+        .run_tests()
+        .map_err(|source| Error::TestRun { source })?;
+    task.check_tests_run(&mut report);
+    Ok(report)
+}
+
+/// Returns the files of the task directory that are not part of the assignment.
+fn excluded_files(task: &TaskInfo) -> Result<Vec<String>, Error> {
+    let mut files = vec![TASK_CONFIG_FILE.to_string()];
+    for pattern in &task.grading_test_patterns() {
+        files.extend(crate::filesystem::files_matching(&task.dir, pattern)?);
+    }
+    Ok(files)
+}
+
+/// Returns the marker of the given line if it is a begin or end marker comment.
+fn marker(line: &str) -> Option<&str> {
+    let comment = line.trim();
+    let comment = comment
+        .strip_prefix("//")
+        .or_else(|| comment.strip_prefix('#'))?;
+    let marker = comment.trim();
+    [BEGIN_SOLUTION, END_SOLUTION]
+        .into_iter()
+        .find(|&m| m == marker)
+}
+
+/// Removes the solution blocks from the given text, including the marker lines,
+/// and replaces each block by the given placeholder, indented like the begin marker.
+/// Returns an error message if the markers don't match.
+fn strip_solutions(text: &str, placeholder: Option<&str>) -> Result<String, String> {
+    let mut stripped = String::with_capacity(text.len());
+    let mut block_start = None;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        match (marker(line), block_start) {
+            (Some(BEGIN_SOLUTION), None) => {
+                block_start = Some(index + 1);
+                if let Some(placeholder) = placeholder {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    for placeholder_line in placeholder.lines() {
+                        stripped.push_str(indent);
+                        stripped.push_str(placeholder_line);
+                        stripped.push('\n');
+                    }
+                }
+            }
+            (Some(BEGIN_SOLUTION), Some(start)) => {
+                return Err(format!(
+                    "{} in line {} inside the solution block starting in line {}",
+                    BEGIN_SOLUTION,
+                    index + 1,
+                    start
+                ));
+            }
+            (Some(_), Some(_)) => block_start = None,
+            (Some(_), None) => {
+                return Err(format!(
+                    "{} in line {} without {}",
+                    END_SOLUTION,
+                    index + 1,
+                    BEGIN_SOLUTION
+                ));
+            }
+            (None, Some(_)) => {}
+            (None, None) => stripped.push_str(line),
+        }
+    }
+    match block_start {
+        Some(start) => Err(format!(
+            "{} in line {} without {}",
+            BEGIN_SOLUTION, start, END_SOLUTION
+        )),
+        None => Ok(stripped),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "package task_2\n\
+        \n\
+        func Factorial(n int) int {\n\
+        \t//begin:solution\n\
+        \tif n == 0 {\n\
+        \t\treturn 1\n\
+        \t}\n\
+        \treturn n * Factorial(n-1)\n\
+        \t//end:solution\n\
+        }\n";
+
+    #[test]
+    fn strip_solutions_with_placeholder() {
+        assert_eq!(
+            strip_solutions(SOURCE, Some("panic(\"TODO\")")).unwrap(),
+            "package task_2\n\nfunc Factorial(n int) int {\n\tpanic(\"TODO\")\n}\n"
+        );
+        assert_eq!(
+            strip_solutions(SOURCE, None).unwrap(),
+            "package task_2\n\nfunc Factorial(n int) int {\n}\n"
+        );
+    }
+
+    #[test]
+    fn strip_solutions_unmatched_markers() {
+        let error = strip_solutions("# begin:solution\nx = 1\n", None).unwrap_err();
+        assert_eq!(error, "begin:solution in line 1 without end:solution");
+
+        let error = strip_solutions("x = 1\n// end:solution\n", None).unwrap_err();
+        assert_eq!(error, "end:solution in line 2 without begin:solution");
+    }
+
+    /// Templates that build and fail the tests pass the check,
+    /// templates that pass the tests or don't build don't.
+    #[cfg(unix)]
+    #[test]
+    fn check_templates() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let tasks = [
+            ("task_1", "#begin:solution\nsolved\n#end:solution\n", ""),
+            ("task_2", "solved\n", ""),
+            (
+                "task_3",
+                "#begin:solution\nsolved\n#end:solution\n",
+                "build_command = \"grep -q solved task_3\"\n",
+            ),
+        ];
+        for (task, source, build_command) in tasks {
+            let task_dir = exam_info.tasks_dir().join(task);
+            fs::create_dir_all(&task_dir).unwrap();
+            fs::write(task_dir.join(task), source).unwrap();
+            fs::write(
+                task_dir.join(TASK_CONFIG_FILE),
+                format!(
+                    "[runner]\nlanguage = \"command\"\ntest_command = \"grep -q solved {}\"\n\
+                    source_file = \"{{task}}\"\n{}",
+                    task, build_command
+                ),
+            )
+            .unwrap();
+        }
+
+        let checks = AssignmentBuilder::new(exam_info).check_templates().unwrap();
+
+        let results: Vec<(&str, bool)> = checks
+            .iter()
+            .map(|check| (check.task.as_str(), check.passed()))
+            .collect();
+        assert_eq!(
+            results,
+            [("task_1", true), ("task_2", false), ("task_3", false)]
+        );
+        assert!(checks[1]
+            .to_string()
+            .contains("the tests pass with the template"));
+        assert!(checks[2].to_string().contains("doesn't build"));
+    }
+
+    #[test]
+    fn build_testdata_go_exam() {
+        let exam_dir = std::env::current_dir().unwrap().join("testdata/go-exam");
+        let exam_info = ExamInfo::from_config_file(&exam_dir).unwrap();
+        let out_dir = tempfile::tempdir().unwrap();
+        let dir = out_dir.path().join("assignment");
+        let zip_file = out_dir.path().join("assignment.zip");
+
+        AssignmentBuilder::new(exam_info)
+            .with_placeholder(Some("panic(\"TODO\")".to_string()))
+            .build_zip(&dir, &zip_file)
+            .unwrap();
+
+        let files = crate::filesystem::files_with_suffix_recursive(&dir, "").unwrap();
+        assert!(files.contains(&"go.mod".to_string()), "{:?}", files);
+        assert!(files.contains(&"task_2/task_2_test.go".to_string()));
+        assert!(!files.iter().any(|file| file.ends_with("_grading_test.go")));
+        let task_2 = fs::read_to_string(dir.join("task_2/task_2.go")).unwrap();
+        assert!(task_2.contains("\tpanic(\"TODO\")\n"), "{}", task_2);
+        assert!(!task_2.contains("Factorial(n-1)"), "{}", task_2);
+
+        let zip = zip::ZipArchive::new(fs::File::open(&zip_file).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        let mut expected: Vec<String> = files
+            .iter()
+            .map(|file| format!("assignment/{}", file))
+            .collect();
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+}
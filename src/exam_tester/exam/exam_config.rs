@@ -0,0 +1,431 @@
+use crate::exam_tester::exam::{FilePrecedence, TestIsolation};
+use crate::exam_tester::process::{Language, ResourceLimits, Sandbox};
+use crate::Error;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The contents of an exam configuration file (`exam.toml`).
+///
+/// All entries are optional, missing entries keep their defaults.
+/// A complete example:
+///
+/// ```toml
+/// timeout = 30
+/// test_patterns = ["*_test.go"]
+///
+/// [directories]
+/// submissions = "abgaben"
+/// tasks = "aufgaben"
+/// grading = "bewertung"
+///
+/// [runner]
+/// language = "go"
+///
+/// [grading]
+/// annotate = true
+/// messages = "de"
+/// test_isolation = "exclude"
+/// file_precedence = "grading"
+///
+/// [limits]
+/// memory_mb = 1024
+/// output_kb = 1024
+///
+/// [sandbox]
+/// read_only = ["/opt/jdk"]
+///
+/// [tasks.task_2]
+/// timeout = 2.5
+///
+/// [tasks.task_2.limits]
+/// memory_mb = 4096
+///
+/// [tasks.task_3.runner]
+/// language = "java"
+/// junit_jar = "lib/junit-platform-console-standalone.jar"
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExamConfig {
+    /// Directories of the exam, relative to the base directory.
+    pub directories: DirectoriesConfig,
+    /// The test timeout in seconds.
+    #[serde(deserialize_with = "deserialize_timeout")]
+    pub timeout: Option<Duration>,
+    /// Patterns of the test files copied from the tasks to the submissions.
+    /// If not set, the patterns of the task's language are used.
+    pub test_patterns: Option<Vec<String>>,
+    /// The language (and runner configuration) of all tasks.
+    /// If not set, the language is detected per task.
+    pub runner: Option<Language>,
+    /// Options for annotating the submissions with the results.
+    pub grading: GradingOptions,
+    /// Resource limits for the test runs, none by default.
+    pub limits: ResourceLimits,
+    /// The sandbox the tests are run in. If not set, the tests are not sandboxed.
+    pub sandbox: Option<Sandbox>,
+    /// Settings for single tasks, overriding the settings of the exam.
+    pub tasks: BTreeMap<String, TaskConfig>,
+}
+
+/// Directory overrides in an exam configuration file.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DirectoriesConfig {
+    pub submissions: Option<PathBuf>,
+    pub tasks: Option<PathBuf>,
+    pub grading: Option<PathBuf>,
+}
+
+/// Settings for a single task, overriding the settings of the exam.
+///
+/// The settings are read from the task's configuration file (`task.toml`)
+/// and from the `[tasks.<name>]` tables of the exam configuration file,
+/// where the latter take precedence. An example `task.toml`:
+///
+/// ```toml
+/// points = 10
+/// timeout = 5
+/// grading_tests = ["*_grading_test.go"]
+/// solution_files = ["list.go", "list_sort.go"]
+///
+/// [runner]
+/// language = "go"
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskConfig {
+    /// The maximum number of points for the task.
+    pub points: Option<f64>,
+    /// The test timeout in seconds.
+    #[serde(deserialize_with = "deserialize_timeout")]
+    pub timeout: Option<Duration>,
+    /// Patterns of the test files copied from the task to the submissions.
+    pub test_patterns: Option<Vec<String>>,
+    /// Patterns of the tests that decide the grading, e.g. `*_grading_test.go`.
+    pub grading_tests: Option<Vec<String>>,
+    /// The files a solution of the task consists of, relative to the task directory.
+    pub solution_files: Option<Vec<String>>,
+    /// The language (and runner configuration) of the task.
+    pub runner: Option<Language>,
+    /// Resource limits for the test runs, overriding single limits of the exam.
+    pub limits: ResourceLimits,
+}
+
+impl TaskConfig {
+    /// Parses a task configuration from the contents of a TOML file.
+    pub fn parse(config: &str) -> Result<Self, Error> {
+        toml::from_str(config).map_err(|e| Error::Config {
+            path: None,
+            message: e.to_string(),
+        })
+    }
+
+    /// Reads and parses the task configuration file at the given path.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let config = std::fs::read_to_string(path).map_err(|e| Error::io("read", path, e))?;
+        toml::from_str(&config).map_err(|e| Error::Config {
+            path: Some(path.to_path_buf()),
+            message: e.to_string(),
+        })
+    }
+
+    /// Returns this configuration with the settings that are not set
+    /// taken from the given configuration.
+    pub fn or(self, other: TaskConfig) -> TaskConfig {
+        TaskConfig {
+            points: self.points.or(other.points),
+            timeout: self.timeout.or(other.timeout),
+            test_patterns: self.test_patterns.or(other.test_patterns),
+            grading_tests: self.grading_tests.or(other.grading_tests),
+            solution_files: self.solution_files.or(other.solution_files),
+            runner: self.runner.or(other.runner),
+            limits: self.limits.or(other.limits),
+        }
+    }
+}
+
+/// Options for annotating the submissions with the test results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GradingOptions {
+    /// Whether the result is appended to the source file of each submission.
+    pub annotate: bool,
+    /// The language of the result messages.
+    pub messages: MessageLanguage,
+    /// How the tests handed in by the students are handled in the test runs.
+    pub test_isolation: TestIsolation,
+    /// Which version of a file is kept if a submission and a task contain different versions.
+    pub file_precedence: FilePrecedence,
+}
+
+impl Default for GradingOptions {
+    fn default() -> Self {
+        Self {
+            annotate: true,
+            messages: MessageLanguage::default(),
+            test_isolation: TestIsolation::default(),
+            file_precedence: FilePrecedence::default(),
+        }
+    }
+}
+
+/// The language of result messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageLanguage {
+    #[default]
+    De,
+    En,
+}
+
+impl ExamConfig {
+    /// The configuration file written for new exams, spelling out the defaults.
+    pub const TEMPLATE: &'static str = r#"# Configuration of the exam, see the documentation of `ExamConfig`.
+# All entries are optional, the values below are the defaults.
+
+# Test timeout in seconds.
+timeout = 30
+
+# Patterns of the test files copied from the tasks to the submissions.
+# If not set, the patterns of each task's language are used (e.g. `*_test.go`).
+# test_patterns = ["*_test.go"]
+
+[directories]
+submissions = "abgaben"
+tasks = "aufgaben"
+grading = "bewertung"
+
+# The language of all tasks. If not set, the language is detected per task.
+# [runner]
+# language = "go"
+
+[grading]
+# Whether the results are appended to the solution files.
+annotate = true
+# The language of the result messages ("de" or "en").
+messages = "de"
+# How the tests handed in by the students are handled in the test runs:
+# "keep" runs them together with the tests of the task, "exclude", "quarantine" and
+# "rename" take them out, so only the grading tests of the task decide the result.
+test_isolation = "keep"
+# Which version of a file is kept if a submission and a task contain different versions
+# (e.g. a modified test): "submission", "grading" or "error" (keep and report the conflict).
+file_precedence = "submission"
+
+# Resource limits for the test runs. If not set, there are no limits.
+[limits]
+# Maximum address space of each process in MiB.
+# memory_mb = 1024
+# Maximum CPU time of each process in seconds.
+# cpu_seconds = 60
+# Maximum number of processes of the user running the tests.
+# processes = 256
+# Maximum size of written files in MiB.
+# file_size_mb = 64
+# Maximum size of the captured stdout and stderr in KiB.
+# output_kb = 1024
+
+# Runs the tests in a sandbox without network access, using bubblewrap.
+# Besides the system directories, the toolchain and the task directory,
+# only the configured directories are available in the sandbox.
+# [sandbox]
+# read_only = ["/opt/jdk"]
+# writable = []
+
+# Settings for single tasks, see also `task.toml` in the task directories.
+# [tasks.task_1]
+# timeout = 10
+"#;
+
+    /// Parses an exam configuration from the contents of a TOML file.
+    pub fn parse(config: &str) -> Result<Self, Error> {
+        toml::from_str(config).map_err(|e| Error::Config {
+            path: None,
+            message: e.to_string(),
+        })
+    }
+
+    /// Reads and parses the exam configuration file at the given path.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let config = std::fs::read_to_string(path).map_err(|e| Error::io("read", path, e))?;
+        toml::from_str(&config).map_err(|e| Error::Config {
+            path: Some(path.to_path_buf()),
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Reads a timeout given in seconds, either as an integer or as a float.
+fn deserialize_timeout<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Integer(u64),
+        Float(f64),
+    }
+
+    let duration = match Seconds::deserialize(deserializer)? {
+        Seconds::Integer(secs) => Duration::from_secs(secs),
+        Seconds::Float(secs) => {
+            Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)?
+        }
+    };
+    Ok(Some(duration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exam_tester::process::{JavaConfig, ReportFormat};
+
+    #[test]
+    fn empty_config() {
+        assert_eq!(ExamConfig::parse("").unwrap(), ExamConfig::default());
+    }
+
+    #[test]
+    fn full_config() {
+        let config = ExamConfig::parse(
+            r#"
+            timeout = 10
+            test_patterns = ["*_test.go"]
+
+            [directories]
+            submissions = "submissions"
+
+            [runner]
+            language = "go"
+
+            [grading]
+            annotate = false
+            messages = "en"
+            test_isolation = "rename"
+            file_precedence = "error"
+
+            [limits]
+            memory_mb = 512
+            output_kb = 64
+
+            [sandbox]
+            read_only = ["/opt/go"]
+
+            [tasks.task_1]
+            timeout = 0.5
+            test_patterns = ["tests/*.sh"]
+
+            [tasks.task_1.limits]
+            memory_mb = 2048
+
+            [tasks.task_1.runner]
+            language = "command"
+            test_command = "bats --tap tests"
+            report_format = "tap"
+            source_file = "{task}.sh"
+
+            [tasks.task_2.runner]
+            language = "java"
+            junit_jar = "lib/junit.jar"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.directories.submissions, Some("submissions".into()));
+        assert_eq!(config.directories.grading, None);
+        assert_eq!(config.runner, Some(Language::Go));
+        assert!(!config.grading.annotate);
+        assert_eq!(config.grading.messages, MessageLanguage::En);
+        assert_eq!(config.grading.test_isolation, TestIsolation::Rename);
+        assert_eq!(config.grading.file_precedence, FilePrecedence::Error);
+
+        assert_eq!(config.limits.memory_mb, Some(512));
+        assert_eq!(config.limits.output_kb, Some(64));
+        let sandbox = config.sandbox.unwrap();
+        assert_eq!(sandbox.bwrap, PathBuf::from("bwrap"));
+        assert_eq!(sandbox.read_only, vec![PathBuf::from("/opt/go")]);
+
+        let task_1 = &config.tasks["task_1"];
+        assert_eq!(task_1.timeout, Some(Duration::from_millis(500)));
+        assert_eq!(task_1.limits.memory_mb, Some(2048));
+        assert_eq!(task_1.limits.output_kb, None);
+        let Some(Language::Command(command)) = &task_1.runner else {
+            panic!("expected command runner, got {:?}", task_1.runner);
+        };
+        assert_eq!(command.test_command, "bats --tap tests");
+        assert_eq!(command.report_format, ReportFormat::Tap);
+        assert_eq!(command.source_file, "{task}.sh");
+
+        assert_eq!(
+            config.tasks["task_2"].runner,
+            Some(Language::Java(JavaConfig::new("lib/junit.jar")))
+        );
+    }
+
+    #[test]
+    fn task_config_or() {
+        let task_toml = TaskConfig::parse(
+            r#"
+            points = 10
+            timeout = 5
+            solution_files = ["list.go"]
+            "#,
+        )
+        .unwrap();
+        let exam_toml = TaskConfig::parse("timeout = 2").unwrap();
+
+        let config = exam_toml.or(task_toml);
+
+        assert_eq!(config.points, Some(10.0));
+        assert_eq!(config.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(config.solution_files, Some(vec!["list.go".to_string()]));
+        assert_eq!(config.grading_tests, None);
+    }
+
+    #[test]
+    fn template_is_default() {
+        let config = ExamConfig::parse(ExamConfig::TEMPLATE).unwrap();
+
+        assert_eq!(config.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(config.grading, GradingOptions::default());
+        assert_eq!(config.runner, None);
+        assert_eq!(config.limits, ResourceLimits::default());
+        assert_eq!(config.sandbox, None);
+    }
+
+    #[test]
+    fn unknown_entry() {
+        let error = ExamConfig::parse("timout = 10").unwrap_err().to_string();
+
+        assert!(error.contains("unknown field `timout`"), "{}", error);
+    }
+
+    #[test]
+    fn command_without_source_file() {
+        let error = ExamConfig::parse("[runner]\nlanguage = \"command\"\ntest_command = \"true\"")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("missing field `source_file`"), "{}", error);
+    }
+
+    #[test]
+    fn command_without_test_command() {
+        let error =
+            ExamConfig::parse("[runner]\nlanguage = \"command\"\nsource_file = \"{task}.sh\"")
+                .unwrap_err()
+                .to_string();
+
+        assert!(error.contains("missing field `test_command`"), "{}", error);
+    }
+
+    #[test]
+    fn invalid_timeout() {
+        assert!(ExamConfig::parse("timeout = -1.0").is_err());
+    }
+}
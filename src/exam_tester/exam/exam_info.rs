@@ -1,13 +1,32 @@
-use std::path::PathBuf;
+use crate::exam_tester::exam::{
+    ExamConfig, GradingOptions, ResultStore, TaskConfig, TaskInfo, TASK_CONFIG_FILE,
+};
+use crate::exam_tester::process::{Language, ResourceLimits, Sandbox};
+use crate::Error;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-#[derive(Debug)]
+/// Name of the exam configuration file in the base directory.
+pub const CONFIG_FILE: &str = "exam.toml";
+
+/// Name of the directory in the grading directory where src2exam keeps its state,
+/// e.g. the results of the test runs.
+pub const STATE_DIR: &str = ".src2exam";
+
+#[derive(Debug, Clone)]
 pub struct ExamInfo {
     base_dir: PathBuf,
     custom_tasks_dir: Option<PathBuf>,
     custom_submissions_dir: Option<PathBuf>,
     custom_grading_dir: Option<PathBuf>,
     test_timeout: Option<Duration>,
+    test_patterns: Option<Vec<String>>,
+    language: Option<Language>,
+    grading_options: GradingOptions,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+    tasks: BTreeMap<String, TaskConfig>,
 }
 
 impl ExamInfo {
@@ -19,9 +38,57 @@ impl ExamInfo {
         }
     }
 
-    /// Returns the default base directory to use for the exam.
+    /// Creates a new `ExamInfo` instance with the given base directory,
+    /// configured by the exam configuration file ([`CONFIG_FILE`]) in the base directory.
+    ///
+    /// Relative paths in the configuration (directories, the JUnit jar for Java)
+    /// are relative to the base directory.
+    pub fn from_config_file<T: Into<PathBuf>>(base_dir: T) -> Result<Self, Error> {
+        let mut exam_info = Self::new(base_dir);
+        let config = ExamConfig::from_file(&exam_info.base_dir.join(CONFIG_FILE))?;
+        exam_info.apply_config(config);
+        Ok(exam_info)
+    }
+
+    /// Applies the given configuration, overriding the current settings.
+    pub fn apply_config(&mut self, config: ExamConfig) {
+        let base_dir = self.base_dir.clone();
+        if let Some(dir) = config.directories.submissions {
+            self.set_submissions_dir(base_dir.join(dir));
+        }
+        if let Some(dir) = config.directories.tasks {
+            self.set_tasks_dir(base_dir.join(dir));
+        }
+        if let Some(dir) = config.directories.grading {
+            self.set_grading_dir(base_dir.join(dir));
+        }
+        if let Some(timeout) = config.timeout {
+            self.set_test_timeout(timeout);
+        }
+        if let Some(patterns) = config.test_patterns {
+            self.set_test_patterns(patterns);
+        }
+        if let Some(language) = config.runner {
+            self.set_language(resolve_paths(language, &base_dir));
+        }
+        self.grading_options = config.grading;
+        self.limits = config.limits;
+        if let Some(sandbox) = config.sandbox {
+            self.set_sandbox(sandbox);
+        }
+        for (task_name, mut task) in config.tasks {
+            task.runner = task
+                .runner
+                .map(|language| resolve_paths(language, &base_dir));
+            let current = self.tasks.remove(&task_name).unwrap_or_default();
+            self.tasks.insert(task_name, task.or(current));
+        }
+    }
+
+    /// Returns the default base directory to use for the exam, i.e. the current directory
+    /// (or `.` if the current directory cannot be determined).
     pub fn base_dir_default() -> PathBuf {
-        std::env::current_dir().unwrap()
+        std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
     }
 
     /// Returns the base directory of the exam.
@@ -87,11 +154,41 @@ impl ExamInfo {
         self.custom_grading_dir = Some(dir.into());
     }
 
+    /// Returns the store for the results of the test runs,
+    /// which is kept in the grading directory.
+    pub fn result_store(&self) -> ResultStore {
+        ResultStore::new(self.grading_dir().join(STATE_DIR).join("results"))
+    }
+
+    /// Returns the file the conflicts between submissions and tasks are logged to,
+    /// which is kept in the grading directory. See [`FileConflict`].
+    ///
+    /// [`FileConflict`]: crate::exam_tester::exam::FileConflict
+    pub fn conflict_log(&self) -> PathBuf {
+        self.grading_dir().join(STATE_DIR).join("conflicts.jsonl")
+    }
+
+    /// Sets up a new exam in the base directory:
+    /// Creates the submissions and tasks directories
+    /// and an exam configuration file ([`CONFIG_FILE`]) with the default settings.
+    /// Existing directories and files are kept.
+    pub fn init(&self) -> Result<(), Error> {
+        for dir in [self.submissions_dir(), self.tasks_dir()] {
+            std::fs::create_dir_all(&dir).map_err(|e| Error::io("create directory", &dir, e))?;
+        }
+        let config_file = self.base_dir.join(CONFIG_FILE);
+        if !config_file.exists() {
+            std::fs::write(&config_file, ExamConfig::TEMPLATE)
+                .map_err(|e| Error::io("write", &config_file, e))?;
+        }
+        Ok(())
+    }
+
     /// Returns the student names for the exam.
     /// Each subdirectory name in the submissions directory
     /// is expected to be a student's name.
     /// The names are sorted in lexicographical order.
-    pub fn student_names(&self) -> Result<Vec<String>, String> {
+    pub fn student_names(&self) -> Result<Vec<String>, Error> {
         let mut names = crate::filesystem::subdir_names(&self.submissions_dir())?;
         names.sort();
         Ok(names)
@@ -101,20 +198,157 @@ impl ExamInfo {
     /// Each subdirectory in the tasks directory is expected to be a task.
     /// The task name is the subdirectory name.
     /// The task names are sorted in lexicographical order.
-    pub fn task_names(&self) -> Result<Vec<String>, String> {
+    pub fn task_names(&self) -> Result<Vec<String>, Error> {
         let mut tasks = crate::filesystem::subdir_names(&self.tasks_dir())?;
         tasks.sort();
         Ok(tasks)
     }
 
+    /// Returns the tasks for the exam with their settings, sorted by name.
+    /// See [`ExamInfo::task`].
+    pub fn tasks(&self) -> Result<Vec<TaskInfo>, Error> {
+        self.task_names()?
+            .iter()
+            .map(|task_name| self.task(task_name))
+            .collect()
+    }
+
+    /// Returns the given task with its settings.
+    ///
+    /// The settings are taken from the `[tasks.<name>]` table of the exam configuration,
+    /// then from the task's configuration file ([`TASK_CONFIG_FILE`]) if there is one,
+    /// and then from the settings of the exam.
+    /// Relative paths in the task's configuration file are relative to the task directory.
+    /// If no language is set, the language is detected from the files in the task directory.
+    pub fn task(&self, task_name: &str) -> Result<TaskInfo, Error> {
+        let dir = self.tasks_dir().join(task_name);
+        let config_file = dir.join(TASK_CONFIG_FILE);
+        let mut task_config = if config_file.is_file() {
+            TaskConfig::from_file(&config_file)?
+        } else {
+            TaskConfig::default()
+        };
+        task_config.runner = task_config
+            .runner
+            .map(|language| resolve_paths(language, &dir));
+        if let Some(exam_task_config) = self.tasks.get(task_name) {
+            task_config = exam_task_config.clone().or(task_config);
+        }
+
+        let language = task_config
+            .runner
+            .or_else(|| self.language.clone())
+            .or_else(|| Language::detect(&dir));
+        Ok(TaskInfo {
+            name: task_name.to_string(),
+            points: task_config.points,
+            timeout: task_config.timeout.unwrap_or_else(|| self.test_timeout()),
+            language,
+            test_patterns: task_config.test_patterns.or(self.test_patterns.clone()),
+            grading_tests: task_config.grading_tests.unwrap_or_default(),
+            solution_files: task_config.solution_files,
+            limits: task_config.limits.or(self.limits),
+            sandbox: self.sandbox.clone(),
+            dir,
+        })
+    }
+
     /// Returns the default test timeout for the exam.
     pub fn test_timeout_default() -> Duration {
         Duration::from_secs(30)
     }
 
+    /// Returns the test timeout for the exam.
+    /// Uses the default timeout if no custom timeout is set.
     pub fn test_timeout(&self) -> Duration {
-        self.test_timeout
-            .unwrap_or_else(|| Self::test_timeout_default())
+        self.test_timeout.unwrap_or_else(Self::test_timeout_default)
+    }
+
+    /// Returns the test timeout for the given task,
+    /// which is the timeout set for the task or else the timeout of the exam.
+    /// Falls back to the timeout of the exam if the task's configuration can't be read;
+    /// use [`ExamInfo::task`] to get all settings of a task.
+    pub fn task_timeout(&self, task_name: &str) -> Duration {
+        self.task(task_name)
+            .map_or_else(|_| self.test_timeout(), |task| task.timeout)
+    }
+
+    /// Returns the patterns of the test files for the given task if they were set explicitly,
+    /// either for the task itself or for the whole exam.
+    /// If no patterns are set, the patterns of the task's language are used.
+    /// Falls back to the patterns of the exam if the task's configuration can't be read;
+    /// use [`ExamInfo::task`] to get all settings of a task.
+    pub fn test_patterns(&self, task_name: &str) -> Option<Vec<String>> {
+        self.task(task_name)
+            .map_or_else(|_| self.test_patterns.clone(), |task| task.test_patterns)
+    }
+
+    /// Sets the test timeout for the exam.
+    pub fn set_test_timeout(&mut self, timeout: Duration) {
+        self.test_timeout = Some(timeout);
+    }
+
+    /// Sets the patterns of the test files for all tasks of the exam.
+    pub fn set_test_patterns(&mut self, patterns: Vec<String>) {
+        self.test_patterns = Some(patterns);
+    }
+
+    /// Returns the options for annotating the submissions with the results.
+    pub fn grading_options(&self) -> &GradingOptions {
+        &self.grading_options
+    }
+
+    /// Sets the options for annotating the submissions with the results.
+    pub fn set_grading_options(&mut self, options: GradingOptions) {
+        self.grading_options = options;
+    }
+
+    /// Returns the resource limits for the test runs of the exam.
+    /// Tasks may override single limits, see [`TaskInfo::limits`].
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Sets the resource limits for the test runs of the exam.
+    pub fn set_limits(&mut self, limits: ResourceLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns the sandbox the tests are run in, if any.
+    pub fn sandbox(&self) -> Option<&Sandbox> {
+        self.sandbox.as_ref()
+    }
+
+    /// Sets the sandbox the tests of all tasks are run in.
+    pub fn set_sandbox(&mut self, sandbox: Sandbox) {
+        self.sandbox = Some(sandbox);
+    }
+
+    /// Returns the language set for all tasks of the exam, if any.
+    /// If no language is set, the language is detected per task.
+    pub fn language(&self) -> Option<&Language> {
+        self.language.as_ref()
+    }
+
+    /// Sets the language for all tasks of the exam.
+    /// This is required for languages that need configuration, e.g. Java.
+    pub fn set_language(&mut self, language: Language) {
+        self.language = Some(language);
+    }
+
+    /// Returns the language of the given task if it was set explicitly
+    /// in the exam configuration, either for the task itself or for the whole exam.
+    /// Use [`ExamInfo::task`] to also take the task's configuration file into account.
+    pub fn task_language(&self, task_name: &str) -> Option<&Language> {
+        self.tasks
+            .get(task_name)
+            .and_then(|task| task.runner.as_ref())
+            .or(self.language.as_ref())
+    }
+
+    /// Sets the language for a single task, overriding the language of the exam.
+    pub fn set_task_language<S: Into<String>>(&mut self, task_name: S, language: Language) {
+        self.tasks.entry(task_name.into()).or_default().runner = Some(language);
     }
 }
 
@@ -127,13 +361,32 @@ impl Default for ExamInfo {
             custom_submissions_dir: None,
             custom_grading_dir: None,
             test_timeout: None,
+            test_patterns: None,
+            language: None,
+            grading_options: GradingOptions::default(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
+            tasks: BTreeMap::new(),
+        }
+    }
+}
+
+/// Makes the paths in the configuration of the given language relative to the given directory.
+fn resolve_paths(language: Language, dir: &Path) -> Language {
+    match language {
+        Language::Java(mut config) => {
+            config.junit_jar = dir.join(config.junit_jar);
+            config.java_home = config.java_home.map(|java_home| dir.join(java_home));
+            Language::Java(config)
         }
+        language => language,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exam_tester::process::JavaConfig;
 
     #[test]
     fn new_examinfo_dirs() {
@@ -182,6 +435,170 @@ mod tests {
         assert_eq!(exam_info.grading_dir(), custom_grading_dir);
     }
 
+    #[test]
+    fn task_language_overrides() {
+        let mut exam_info = ExamInfo::new("exam_basedir");
+        assert_eq!(exam_info.task_language("task_1"), None);
+
+        exam_info.set_language(Language::Python);
+        exam_info.set_task_language("task_2", Language::Rust);
+
+        assert_eq!(exam_info.task_language("task_1"), Some(&Language::Python));
+        assert_eq!(exam_info.task_language("task_2"), Some(&Language::Rust));
+    }
+
+    #[test]
+    fn task_settings_override_exam_settings() {
+        let mut exam_info = ExamInfo::new("exam_basedir");
+        exam_info.apply_config(
+            ExamConfig::parse(
+                r#"
+                timeout = 5
+                test_patterns = ["*_test.go"]
+
+                [tasks.task_2]
+                timeout = 1.5
+                test_patterns = ["tests/*.rs"]
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let task_1 = exam_info.task("task_1").unwrap();
+        let task_2 = exam_info.task("task_2").unwrap();
+        assert_eq!(task_1.timeout, Duration::from_secs(5));
+        assert_eq!(task_2.timeout, Duration::from_millis(1500));
+        assert_eq!(task_1.test_patterns, Some(vec!["*_test.go".to_string()]));
+        assert_eq!(task_2.test_patterns, Some(vec!["tests/*.rs".to_string()]));
+        assert_eq!(exam_info.task_timeout("task_2"), task_2.timeout);
+        assert_eq!(exam_info.test_patterns("task_1"), task_1.test_patterns);
+    }
+
+    #[test]
+    fn config_paths_relative_to_base_dir() {
+        let mut exam_info = ExamInfo::new("exam_basedir");
+        exam_info.apply_config(
+            ExamConfig::parse(
+                r#"
+                [directories]
+                submissions = "submissions"
+                grading = "/tmp/grading"
+
+                [runner]
+                language = "java"
+                junit_jar = "lib/junit.jar"
+                "#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(
+            exam_info.submissions_dir(),
+            PathBuf::from("exam_basedir/submissions")
+        );
+        assert_eq!(
+            exam_info.tasks_dir(),
+            PathBuf::from("exam_basedir/aufgaben")
+        );
+        assert_eq!(exam_info.grading_dir(), PathBuf::from("/tmp/grading"));
+        assert_eq!(
+            exam_info.task_language("task_1"),
+            Some(&Language::Java(JavaConfig::new(
+                "exam_basedir/lib/junit.jar"
+            )))
+        );
+    }
+
+    #[test]
+    fn config_file_testdata_go_exam() {
+        let exam_dir = std::env::current_dir().unwrap().join("testdata/go-exam");
+
+        let exam_info = ExamInfo::from_config_file(&exam_dir).unwrap();
+
+        assert_eq!(exam_info.submissions_dir(), exam_dir.join("abgaben"));
+        assert_eq!(exam_info.test_timeout(), Duration::from_secs(30));
+        assert_eq!(exam_info.task_language("task_1"), Some(&Language::Go));
+        assert!(exam_info.grading_options().annotate);
+    }
+
+    #[test]
+    fn task_config_file() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let task_dir = exam_dir.path().join("aufgaben/task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(
+            task_dir.join(TASK_CONFIG_FILE),
+            r#"
+            points = 10
+            timeout = 5
+            grading_tests = ["*_grading_test.go"]
+            solution_files = ["list.go", "sort.go"]
+
+            [runner]
+            language = "java"
+            junit_jar = "junit.jar"
+
+            [limits]
+            memory_mb = 2048
+            "#,
+        )
+        .unwrap();
+        let mut exam_info = ExamInfo::new(exam_dir.path());
+        exam_info.apply_config(
+            ExamConfig::parse(
+                "[limits]\nmemory_mb = 512\ncpu_seconds = 10\n[tasks.task_1]\ntimeout = 2",
+            )
+            .unwrap(),
+        );
+
+        let tasks = exam_info.tasks().unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        let task = &tasks[0];
+        assert_eq!(task.name, "task_1");
+        assert_eq!(task.points, Some(10.0));
+        assert_eq!(task.timeout, Duration::from_secs(2));
+        assert_eq!(task.grading_tests, vec!["*_grading_test.go"]);
+        assert_eq!(task.solution_files(), vec!["list.go", "sort.go"]);
+        assert_eq!(task.limits.memory_mb, Some(2048));
+        assert_eq!(task.limits.cpu_seconds, Some(10));
+        assert_eq!(
+            task.language,
+            Some(Language::Java(JavaConfig::new(task_dir.join("junit.jar"))))
+        );
+    }
+
+    #[test]
+    fn task_language_detected() {
+        let exam_dir = std::env::current_dir().unwrap().join("testdata/go-exam");
+        let exam_info = ExamInfo::new(exam_dir);
+
+        let task = exam_info.task("task_1").unwrap();
+
+        assert_eq!(task.language, Some(Language::Go));
+        assert_eq!(task.timeout, ExamInfo::test_timeout_default());
+        assert_eq!(task.solution_files(), vec!["task_1.go"]);
+    }
+
+    #[test]
+    fn init_exam() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+
+        exam_info.init().unwrap();
+        exam_info.init().unwrap();
+
+        assert!(exam_info.submissions_dir().is_dir());
+        assert!(exam_info.tasks_dir().is_dir());
+        let exam_info = ExamInfo::from_config_file(exam_dir.path()).unwrap();
+        assert_eq!(exam_info.student_names().unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn config_file_missing() {
+        assert!(ExamInfo::from_config_file("non_existent_dir").is_err());
+    }
+
     #[test]
     fn student_names_testdata_go_exam() {
         let base_dir = std::env::current_dir().unwrap();
@@ -210,9 +627,15 @@ mod tests {
         exam_info.set_submissions_dir("non_existent_dir");
         let student_names = exam_info.student_names();
 
-        assert_eq!(
-            student_names,
-            Err("No such file or directory (os error 2)".to_string())
+        assert!(
+            matches!(
+                &student_names,
+                Err(Error::Io { operation: "read directory", path, source })
+                    if path.as_path() == Path::new("non_existent_dir")
+                        && source.kind() == std::io::ErrorKind::NotFound
+            ),
+            "{:?}",
+            student_names
         );
     }
 
@@ -241,9 +664,15 @@ mod tests {
         exam_info.set_tasks_dir("non_existent_dir");
         let task_names = exam_info.task_names();
 
-        assert_eq!(
-            task_names,
-            Err("No such file or directory (os error 2)".to_string())
+        assert!(
+            matches!(
+                &task_names,
+                Err(Error::Io { operation: "read directory", path, source })
+                    if path.as_path() == Path::new("non_existent_dir")
+                        && source.kind() == std::io::ErrorKind::NotFound
+            ),
+            "{:?}",
+            task_names
         );
     }
 }
@@ -1,81 +1,1087 @@
-use crate::exam_tester::exam::ExamInfo;
-use crate::exam_tester::process::GoRunner;
+use crate::exam_tester::exam::{
+    ExamInfo, FileConflict, FileOrigin, FilePrecedence, GradingOptions, IssueKind, MessageLanguage,
+    ProblemReport, ResultTable, StagedTask, TaskInfo, TestIsolation, TestReport, TestResult,
+    VerificationIssue, VerificationReport, VERIFICATION_RUNS,
+};
+use crate::exam_tester::process::{GoBatchRunner, Language, GO_TIMEOUT_GRACE};
+use crate::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Maximum number of build output lines added to the grading message.
+const MAX_BUILD_OUTPUT_LINES: usize = 50;
 
 pub struct ExamTester {
     exam_info: ExamInfo,
+    jobs: usize,
+    go_batch: bool,
+    go_batch_timeout: Option<Duration>,
+    verify: bool,
 }
 
 impl ExamTester {
     pub fn new(exam_info: ExamInfo) -> Self {
-        Self { exam_info }
+        Self {
+            exam_info,
+            jobs: 1,
+            go_batch: false,
+            go_batch_timeout: None,
+            verify: false,
+        }
+    }
+
+    /// Sets the number of test runs executed at the same time (at least 1).
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Sets whether the tests of all Go tasks are run with a single `go test ./...`
+    /// in the grading directory instead of one `go test` per student and task.
+    /// This requires the grading directory to be part of a Go module.
+    /// If a sandbox is configured, the Go tasks are run separately anyway,
+    /// as a single sandbox cannot keep the packages of different students apart.
+    pub fn with_go_batch(mut self, go_batch: bool) -> Self {
+        self.go_batch = go_batch;
+        self
+    }
+
+    /// Sets the maximum duration of the `go test ./...` run in batch mode, if any.
+    /// If the run takes longer, it is killed and the Go tasks are run separately.
+    /// By default, the run is bounded by the timeout of the tasks for building
+    /// and for each round of packages tested in parallel (see [`ExamTester::with_go_batch`]).
+    pub fn with_go_batch_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.go_batch_timeout = timeout;
+        self
+    }
+
+    /// Sets whether the tests are verified against the reference solutions
+    /// before running them on the submissions (see [`ExamTester::verify`]).
+    /// If enabled, [`ExamTester::run_tests`] doesn't run any tests on the submissions
+    /// unless the verification passes.
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Returns the information about the exam.
+    pub fn exam_info(&self) -> &ExamInfo {
+        &self.exam_info
+    }
+
+    /// Returns the language of the given task.
+    /// Uses the language set for the task or the exam if there is one,
+    /// otherwise the language is detected from the files in the task directory.
+    /// See [`ExamInfo::task`].
+    pub fn task_language(&self, task_name: &str) -> Option<Language> {
+        self.exam_info
+            .task(task_name)
+            .ok()
+            .and_then(|task| task.language)
     }
 
     /// Copies the submissions into the grading directory.
-    pub fn copy_submissions(&self) {
+    /// Each student's directory is copied recursively.
+    /// Files that already exist in a different version are only overwritten if they were copied
+    /// from a task and the file precedence of the grading options prefers the submission
+    /// (see [`FilePrecedence`]). Each such conflict is logged.
+    ///
+    /// Submissions that cannot be copied are reported as problems,
+    /// the other submissions are copied anyway.
+    /// Returns an error if the students cannot be determined.
+    pub fn copy_submissions(&self) -> Result<ProblemReport, Error> {
         let submissions_dir = self.exam_info.submissions_dir();
         let grading_dir = self.exam_info.grading_dir();
+        let student_names = self.exam_info.student_names()?;
 
-        crate::filesystem::copy_subdirs(&submissions_dir, &grading_dir);
+        println!("Copying submissions:");
+        println!("  source: {:?}", submissions_dir);
+        println!("  destination: {:?}", grading_dir);
+        let precedence = self.exam_info.grading_options().file_precedence;
+        let tasks_dir = self.exam_info.tasks_dir();
+        let mut problems = ProblemReport::new();
+        let mut conflicts = Vec::new();
+        for student_name in &student_names {
+            let src = submissions_dir.join(student_name);
+            let dest = grading_dir.join(student_name);
+            let result = crate::filesystem::copy_dir(&src, &dest, &mut |_, dest_file| {
+                let file = dest_file.strip_prefix(&dest).unwrap_or(dest_file);
+                let task_file = tasks_dir.join(file);
+                let from_task = task_file.is_file()
+                    && crate::filesystem::same_content(&task_file, dest_file).unwrap_or(false);
+                let existing = if from_task {
+                    FileOrigin::Task
+                } else {
+                    FileOrigin::GradingDir
+                };
+                let overwrite = precedence.overwrites(existing, FileOrigin::Submission);
+                conflicts.push(FileConflict::new(
+                    student_name.as_str(),
+                    file,
+                    existing,
+                    FileOrigin::Submission,
+                    overwrite,
+                ));
+                overwrite
+            });
+            if let Err(e) = result {
+                problems.push(e.in_student(student_name));
+            }
+        }
+        self.log_conflicts(&conflicts, &mut problems);
+        Ok(problems)
     }
 
     /// Copies tests from the tasks directory to the grading directory.
-    /// More preisely, copies all files ending in `_test.go` from
-    /// the tasks to all corresponding submissions in the grading directory.
+    /// More precisely, copies all test files (e.g. files ending in `_test.go` for Go
+    /// or integration tests in `tests/` for Rust, unless configured otherwise,
+    /// and the grading tests of the task) from the tasks to all corresponding submissions in the grading directory.
     ///
     /// The following conditions apply:
-    /// * Will not overwrite any files
+    /// * Will only overwrite files of the submissions if the file precedence of the grading options
+    ///   prefers the tasks (see [`FilePrecedence`]). Each such conflict is logged.
+    ///   Files that differ from the submission are older copies of the task's files
+    ///   (e.g. of a grading test fixed since) and are always overwritten.
     /// * Will create new directories for tasks under known student directories
     ///   (based on the student names reported by the exam info).
     /// * Will not create or copy any other directories or files.
-    pub fn copy_tests(&self) {
-        let tasks_dir = self.exam_info.tasks_dir();
+    /// * Will skip tasks whose language cannot be detected.
+    ///
+    /// Tasks whose language cannot be detected or whose tests cannot be read,
+    /// and tests that cannot be copied to a submission, are reported as problems.
+    /// Returns an error if the students or tasks cannot be determined.
+    pub fn copy_tests(&self) -> Result<ProblemReport, Error> {
         let grading_dir = self.exam_info.grading_dir();
+        let submissions_dir = self.exam_info.submissions_dir();
 
-        let task_names = self.exam_info.task_names().unwrap();
-        let student_names = self.exam_info.student_names().unwrap();
+        let tasks = self.exam_info.tasks()?;
+        let student_names = self.exam_info.student_names()?;
 
-        for task_name in &task_names {
-            let task_dir = tasks_dir.join(task_name);
-            let test_files = crate::filesystem::files_with_suffix(&task_dir, "_test.go").unwrap();
+        let precedence = self.exam_info.grading_options().file_precedence;
+        let mut problems = ProblemReport::new();
+        let mut conflicts = Vec::new();
+        for task in &tasks {
+            if task.language.is_none() {
+                eprintln!("Unknown language for task {}, skipping tests", task.name);
+                problems.push(Error::UnknownLanguage {
+                    task: task.name.clone(),
+                });
+                continue;
+            }
+            let task_dir = &task.dir;
+            let test_files: Result<Vec<Vec<String>>, Error> = task
+                .test_file_patterns()
+                .iter()
+                .map(|pattern| crate::filesystem::files_matching(task_dir, pattern))
+                .collect();
+            let mut test_files: Vec<String> = match test_files {
+                Ok(test_files) => test_files.into_iter().flatten().collect(),
+                Err(e) => {
+                    problems.push(e);
+                    continue;
+                }
+            };
+            test_files.sort();
+            test_files.dedup();
             for student_name in &student_names {
                 let student_dir = grading_dir.join(student_name);
-                let student_task_dir = student_dir.join(task_name);
-                crate::filesystem::copy_files(&test_files, &task_dir, &student_task_dir);
+                let student_task_dir = student_dir.join(&task.name);
+                let result = crate::filesystem::copy_files(
+                    &test_files,
+                    task_dir,
+                    &student_task_dir,
+                    &mut |_, dest_file| {
+                        let file = dest_file.strip_prefix(&student_dir).unwrap_or(dest_file);
+                        let submitted_file = submissions_dir.join(student_name).join(file);
+                        let from_submission = submitted_file.is_file()
+                            && crate::filesystem::same_content(&submitted_file, dest_file)
+                                .unwrap_or(false);
+                        if !from_submission {
+                            // An older version of the task's file, e.g. a grading test fixed since.
+                            return true;
+                        }
+                        let overwrite =
+                            precedence.overwrites(FileOrigin::Submission, FileOrigin::Task);
+                        conflicts.push(FileConflict::new(
+                            student_name.as_str(),
+                            file,
+                            FileOrigin::Submission,
+                            FileOrigin::Task,
+                            overwrite,
+                        ));
+                        overwrite
+                    },
+                );
+                if let Err(e) = result {
+                    problems.push(e.in_task(student_name, &task.name));
+                }
             }
         }
+        self.log_conflicts(&conflicts, &mut problems);
+        Ok(problems)
+    }
+
+    /// Prints the given conflicts and appends them to the conflict log of the exam
+    /// (see [`ExamInfo::conflict_log`]), one JSON object per line.
+    /// Conflicts between a submission and a task are added to the given problems
+    /// if the file precedence demands it, as are failures to write the log.
+    /// Conflicts with files of the grading directory (e.g. annotated solution files
+    /// of an earlier run) are only logged.
+    fn log_conflicts(&self, conflicts: &[FileConflict], problems: &mut ProblemReport) {
+        if conflicts.is_empty() {
+            return;
+        }
+        let precedence = self.exam_info.grading_options().file_precedence;
+        for conflict in conflicts {
+            println!("  conflict: {}", conflict);
+            let between_submission_and_task = [conflict.kept, conflict.discarded]
+                .iter()
+                .all(|&origin| origin != FileOrigin::GradingDir);
+            if precedence == FilePrecedence::Error && between_submission_and_task {
+                let path = self
+                    .exam_info
+                    .grading_dir()
+                    .join(&conflict.student)
+                    .join(&conflict.file);
+                problems.push(Error::FileConflict { path }.in_student(&conflict.student));
+            }
+        }
+
+        let log_file = self.exam_info.conflict_log();
+        let lines: String = conflicts
+            .iter()
+            .filter_map(|conflict| serde_json::to_string(conflict).ok())
+            .map(|line| line + "\n")
+            .collect();
+        let result = log_file
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&log_file)
+            })
+            .and_then(|mut file| file.write_all(lines.as_bytes()));
+        if let Err(e) = result {
+            problems.push(Error::io("write", &log_file, e));
+        }
     }
 
     /// Runs the tests for all students and tasks in the grading directory.
     ///
     /// The tests and submissions are expected to be in the correct directories.
-    /// I.e. no checks are made for the existence of the files
-    /// and no files are copied or moved.
+    /// I.e. no files are copied or moved within the grading directory.
+    /// Each test run works on a temporary copy of the student's task directory
+    /// (see [`StagedTask`]), so files written during the run don't end up in the grading directory.
+    /// The tests handed in by the student are handled in the copy according to the
+    /// test isolation of the grading options (see [`TestIsolation`]).
+    /// Only the annotations are written to the grading directory.
     ///
+    /// Before the tests are run, each submission is checked (see [`TaskInfo::check_submission`]),
+    /// so tasks that were not handed in or lack a source file are reported as such
+    /// instead of being tested. Test runs without any tests are reported as
+    /// [`TestResult::NoTestsRun`] (see [`TaskInfo::check_tests_run`]).
+    ///
+    /// The test runner is chosen per task based on the task's language
+    /// and runs with the task's timeout.
     /// A message is printed for each test run.
-    /// A short summary of the result is appended to the corresponding source file.
-    /// The filename is assumed to be the task name with the `.go` extension.
-    pub fn run_tests(&self) {
+    /// Unless disabled in the grading options,
+    /// a short summary of the result is appended to the first solution file of the task.
+    /// If no solution files are configured, this is determined by the language,
+    /// e.g. the task name with the `.go` extension for Go.
+    /// The report of each test run is saved in the exam's result store,
+    /// replacing the report of an earlier run.
+    ///
+    /// Up to the configured number of test runs (see [`ExamTester::with_jobs`])
+    /// are executed at the same time. The messages are still printed,
+    /// and the results saved and appended, in the order of students and tasks,
+    /// by the calling thread only.
+    ///
+    /// In Go batch mode (see [`ExamTester::with_go_batch`]),
+    /// the Go tasks of all students are tested before the other tasks.
+    /// Batch mode is not available if the tests are run in a sandbox,
+    /// as all students' packages are tested by a single process.
+    ///
+    /// Test runs that fail (e.g. because the toolchain cannot be started)
+    /// and results that cannot be saved or appended are reported as problems,
+    /// the other tests are run anyway.
+    /// Returns an error if the students or tasks cannot be determined,
+    /// if the configured sandbox cannot be used
+    /// or if the verification is enabled and doesn't pass. No tests are run in this case.
+    pub fn run_tests(&self) -> Result<ProblemReport, Error> {
         let grading_dir = self.exam_info.grading_dir();
-        let student_names = self.exam_info.student_names().unwrap();
-        let task_names = self.exam_info.task_names().unwrap();
+        let student_names = self.exam_info.student_names()?;
+        let tasks = self.exam_info.tasks()?;
+        if let Some(sandbox) = self.exam_info.sandbox() {
+            sandbox.check()?;
+        }
+        if self.verify {
+            let verification = self.verify()?;
+            if !verification.passed() {
+                let tasks = verification.failed_tasks();
+                return Err(Error::Verification {
+                    tasks: tasks.iter().map(|task| task.to_string()).collect(),
+                });
+            }
+        }
 
-        for student_name in &student_names {
-            println!("Running tests for student: {}", student_name);
-            for task_name in &task_names {
-                print!("  {}: ", task_name);
-                let student_task_dir = grading_dir.join(student_name).join(task_name);
+        let runs: Vec<(&str, &TaskInfo)> = student_names
+            .iter()
+            .flat_map(|student_name| tasks.iter().map(move |task| (student_name.as_str(), task)))
+            .collect();
+
+        let mut problems = ProblemReport::new();
+        // Reports of runs that are already finished, by index of the run.
+        let mut finished: BTreeMap<usize, Result<TestReport, Error>> = BTreeMap::new();
+        for (index, (student_name, task)) in runs.iter().enumerate() {
+            let student_task_dir = grading_dir.join(student_name).join(&task.name);
+            match task.check_submission(&student_task_dir) {
+                Ok(None) => {}
+                Ok(Some(report)) => {
+                    finished.insert(index, Ok(report));
+                }
+                Err(e) => {
+                    finished.insert(index, Err(e));
+                }
+            }
+        }
+        if self.go_batch && self.exam_info.sandbox().is_some() {
+            println!("Go batch mode is not available in the sandbox, running Go tasks separately");
+        } else if self.go_batch {
+            let pending_runs: Vec<(&str, &TaskInfo)> = runs
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !finished.contains_key(index))
+                .map(|(_, &run)| run)
+                .collect();
+            let mut batch_reports = self.run_go_batch(&pending_runs, &mut problems);
+            for (index, (student_name, task)) in runs.iter().enumerate() {
+                let key = (student_name.to_string(), task.name.clone());
+                if let (false, Some(mut report)) =
+                    (finished.contains_key(&index), batch_reports.remove(&key))
+                {
+                    task.check_tests_run(&mut report);
+                    finished.insert(index, Ok(report));
+                }
+            }
+        }
+        let pending: Vec<usize> = (0..runs.len())
+            .filter(|index| !finished.contains_key(index))
+            .collect();
+
+        let next_run = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(pending.len()) {
+                let sender = sender.clone();
+                let (runs, pending, next_run) = (&runs, &pending, &next_run);
+                let grading_dir = &grading_dir;
+                let isolation = self.exam_info.grading_options().test_isolation;
+                scope.spawn(move || {
+                    while let Some(&index) = pending.get(next_run.fetch_add(1, Ordering::Relaxed)) {
+                        let (student_name, task) = runs[index];
+                        let student_task_dir = grading_dir.join(student_name).join(&task.name);
+                        let report = run_task(task, &student_task_dir, isolation);
+                        if sender.send((index, report)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            // Results arrive in any order, they are handled in the order of the runs.
+            let mut received = receiver.iter();
+            let mut next_index = 0;
+            while next_index < runs.len() {
+                let Some(report) = finished.remove(&next_index) else {
+                    match received.next() {
+                        Some((index, report)) => finished.insert(index, report),
+                        None => break,
+                    };
+                    continue;
+                };
+                let (student_name, task) = runs[next_index];
+                if tasks.first().is_some_and(|first| first.name == task.name) {
+                    println!("Running tests for student: {}", student_name);
+                }
+                self.finish_run(student_name, task, report, &mut problems);
+                next_index += 1;
+            }
+        });
+        Ok(problems)
+    }
+
+    /// Verifies the tests of all tasks against the reference solutions,
+    /// i.e. runs the tests in a temporary copy of each task directory,
+    /// which is expected to contain the reference solution together with all tests.
+    ///
+    /// The tests of each task are run [`VERIFICATION_RUNS`] times with the task's settings.
+    /// Tests that don't pass, pass only sometimes (i.e. are flaky)
+    /// or take long compared to the timeout are reported (see [`IssueKind`]),
+    /// the latter only as a warning.
+    /// A message is printed for each task.
+    ///
+    /// Returns an error if the tasks cannot be determined
+    /// or if the configured sandbox cannot be used.
+    pub fn verify(&self) -> Result<VerificationReport, Error> {
+        let tasks = self.exam_info.tasks()?;
+        if let Some(sandbox) = self.exam_info.sandbox() {
+            sandbox.check()?;
+        }
+
+        let mut report = VerificationReport::default();
+        for task in &tasks {
+            println!("Verifying tests of task: {}", task.name);
+            let mut runs = Vec::new();
+            let mut kind = None;
+            for _ in 0..VERIFICATION_RUNS {
+                let start = Instant::now();
+                match run_task(task, &task.dir, TestIsolation::Keep) {
+                    Ok(run) => runs.push((run, start.elapsed())),
+                    Err(e) => {
+                        kind = Some(IssueKind::NotRun(e));
+                        break;
+                    }
+                }
+            }
+            report.tasks.push(task.name.clone());
+            match kind.or_else(|| IssueKind::from_runs(&runs, task.timeout)) {
+                Some(kind) => {
+                    let issue = VerificationIssue {
+                        task: task.name.clone(),
+                        kind,
+                    };
+                    println!("  {}", issue);
+                    report.issues.push(issue);
+                }
+                None => println!("  ok"),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Prints the result of a test run, saves the report
+    /// and appends the result to the solution file.
+    /// Adds failed test runs and failures to save or append the result to the given problems.
+    fn finish_run(
+        &self,
+        student_name: &str,
+        task: &TaskInfo,
+        report: Result<TestReport, Error>,
+        problems: &mut ProblemReport,
+    ) {
+        let options = self.exam_info.grading_options();
+        let report = match report {
+            Ok(report) => report,
+            Err(e) => {
+                println!("  {}: {}", task.name, e);
+                problems.push(e.in_task(student_name, &task.name));
+                return;
+            }
+        };
+
+        let result_store = self.exam_info.result_store();
+        if let Err(e) = result_store.save(student_name, &task.name, &report) {
+            eprintln!("Failed to save result: {}", e);
+            problems.push(e.in_task(student_name, &task.name));
+        }
+        println!(
+            "  {}: {}",
+            task.name,
+            report.result().to_string_in(options.messages)
+        );
+        let Some(language) = &task.language else {
+            return;
+        };
+        if let (true, Some(source_file)) = (options.annotate, task.solution_files().first()) {
+            let grading_message = grading_message(options, language, &report);
+            let source_file = self
+                .exam_info
+                .grading_dir()
+                .join(student_name)
+                .join(&task.name)
+                .join(source_file);
+            if let Err(e) = crate::filesystem::append_to_file(&source_file, &grading_message) {
+                eprintln!("Failed to annotate the solution: {}", e);
+                problems.push(e.in_task(student_name, &task.name));
+            }
+        }
+    }
+
+    /// Runs the tests of the given runs of Go tasks with a single `go test ./...`
+    /// in a temporary copy of the grading directory. Returns the reports by student and task name.
+    /// The test isolation is only applied to the given runs, other runs are ignored.
+    ///
+    /// Packages are assigned to students and tasks by their import path,
+    /// which is expected to end with `<student>/<task>`.
+    /// Runs that are missing in the result (e.g. because a student's task directory
+    /// is a module of its own) are run separately afterwards.
+    /// If the batch run fails, the failure is added to the given problems
+    /// and all runs are run separately.
+    fn run_go_batch(
+        &self,
+        runs: &[(&str, &TaskInfo)],
+        problems: &mut ProblemReport,
+    ) -> HashMap<(String, String), TestReport> {
+        // Tasks with limits of their own are run separately, as the limits apply to the whole run.
+        let limits = *self.exam_info.limits();
+        let go_runs: Vec<(&str, &TaskInfo)> = runs
+            .iter()
+            .copied()
+            .filter(|(_, task)| task.language == Some(Language::Go) && task.limits == limits)
+            .collect();
+        let Some(timeout) = go_runs.iter().map(|(_, task)| task.timeout).max() else {
+            return HashMap::new();
+        };
+        // go test builds the packages and then tests as many packages at once as there are CPUs,
+        // each bounded by the timeout.
+        let run_count = go_runs.len();
+        let parallel_runs = thread::available_parallelism().map_or(1, |n| n.get());
+        let rounds = run_count.div_ceil(parallel_runs) + 1;
+        let mut total_timeout =
+            timeout.saturating_mul(rounds.try_into().unwrap_or(u32::MAX)) + GO_TIMEOUT_GRACE;
+        if let Some(go_batch_timeout) = self.go_batch_timeout {
+            total_timeout = total_timeout.min(go_batch_timeout);
+        }
 
-                let runner = GoRunner::new(&student_task_dir, self.exam_info.test_timeout());
-                let test_result = runner.run_tests();
+        println!("Running go test for all Go tasks");
+        let grading_dir = self.exam_info.grading_dir();
+        let staged = match StagedTask::new(&grading_dir, &Language::Go.module_files(&grading_dir)) {
+            Ok(staged) => staged,
+            Err(e) => {
+                println!("failed to copy the grading directory: {}", e);
+                problems.push(e);
+                return HashMap::new();
+            }
+        };
+        let isolation = self.exam_info.grading_options().test_isolation;
+        for &(student_name, task) in &go_runs {
+            let dir = staged.task_dir().join(student_name).join(&task.name);
+            if let Err(e) = isolation.apply(task, &dir) {
+                problems.push(e.in_task(student_name, &task.name));
+            }
+        }
+        let runner = GoBatchRunner::new(staged.task_dir(), timeout).with_limits(limits);
+        let reports = match runner.run_packages(total_timeout) {
+            Ok(reports) => reports,
+            Err(source) => {
+                let e = Error::TestRun { source };
+                println!("{}", e);
+                problems.push(e);
+                return HashMap::new();
+            }
+        };
+        reports
+            .into_iter()
+            .filter_map(|(package, mut report)| {
+                staged.restore_paths(&mut report);
+                let mut segments = package.rsplit('/');
+                let task_name = segments.next()?.to_string();
+                let student_name = segments.next()?.to_string();
+                go_runs
+                    .iter()
+                    .any(|(student, task)| *student == student_name && task.name == task_name)
+                    .then_some(((student_name, task_name), report))
+            })
+            .collect()
+    }
+
+    /// Returns the saved results of all students and tasks,
+    /// without running any tests.
+    pub fn result_table(&self) -> Result<ResultTable, Error> {
+        let result_store = self.exam_info.result_store();
+        let tasks = self.exam_info.task_names()?;
+        let mut rows = Vec::new();
+        for student_name in self.exam_info.student_names()? {
+            let results = tasks
+                .iter()
+                .map(|task_name| {
+                    let report = result_store.load(&student_name, task_name)?;
+                    Ok(report.map(|report| report.result()))
+                })
+                .collect::<Result<_, Error>>()?;
+            rows.push((student_name, results));
+        }
+        Ok(ResultTable { tasks, rows })
+    }
+}
+
+/// Runs the tests of the given task in a temporary copy of the given directory,
+/// so the directory itself is never changed by the test run.
+/// The given test isolation is applied to the copy.
+/// Returns an error if the tests could not be run.
+fn run_task(task: &TaskInfo, dir: &Path, isolation: TestIsolation) -> Result<TestReport, Error> {
+    let Some(language) = &task.language else {
+        return Err(Error::UnknownLanguage {
+            task: task.name.clone(),
+        });
+    };
+    let staged = StagedTask::new(dir, &language.module_files(dir))?;
+    isolation.apply(task, staged.task_dir())?;
+    let mut report = language
+        .runner(
+            staged.task_dir(),
+            task.timeout,
+            task.limits,
+            task.sandbox.as_ref(),
+        )
+        .run_tests()
+        .map_err(|source| Error::TestRun { source })?;
+    staged.restore_paths(&mut report);
+    task.check_tests_run(&mut report);
+    Ok(report)
+}
+
+/// Creates the message appended to the source file of a submission.
+/// For build failures, the build output (e.g. compiler diagnostics) is included
+/// so it is available while grading.
+fn grading_message(options: &GradingOptions, language: &Language, report: &TestReport) -> String {
+    let comment = language.comment_prefix();
+    let result = report.result();
+    let header = match options.messages {
+        MessageLanguage::De => "BEWERTUNG",
+        MessageLanguage::En => "GRADING",
+    };
+    let mut message = format!(
+        "{} {}: \n{} TESTS: {}",
+        comment,
+        header,
+        comment,
+        result.to_string_in(options.messages)
+    );
+
+    if result == TestResult::BuildFailure {
+        for line in report.output.lines().take(MAX_BUILD_OUTPUT_LINES) {
+            message.push_str(format!("\n{} {}", comment, line).trim_end());
+        }
+    }
+    message
+}
 
-                let result_message = test_result.to_string_de();
-                let grading_message = format!("// BEWERTUNG: \n// TESTS: {}", result_message);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                // Print result message and append result to source file.
-                println!("{}", result_message);
-                let source_file = student_task_dir.join(format!("{}.go", task_name));
-                crate::filesystem::append_to_file(&source_file, &grading_message);
+    #[test]
+    fn grading_message_success() {
+        let report = TestReport {
+            passed: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            grading_message(&GradingOptions::default(), &Language::Go, &report),
+            "// BEWERTUNG: \n// TESTS: Ok"
+        );
+    }
+
+    #[test]
+    fn grading_message_build_failure_with_diagnostics() {
+        let report = TestReport::build_failure("task.py:1: SyntaxError\n\n");
+
+        assert_eq!(
+            grading_message(&GradingOptions::default(), &Language::Python, &report),
+            "# BEWERTUNG: \n# TESTS: Build-Fehlschlag\n# task.py:1: SyntaxError\n#"
+        );
+    }
+
+    #[test]
+    fn grading_message_english() {
+        let options = GradingOptions {
+            messages: MessageLanguage::En,
+            ..Default::default()
+        };
+        let report = TestReport {
+            passed: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            grading_message(&options, &Language::Go, &report),
+            "// GRADING: \n// TESTS: Success"
+        );
+    }
+
+    /// Runs a shell based task for several students in parallel:
+    /// The tests pass if the submission contains a file `ok`.
+    #[cfg(unix)]
+    #[test]
+    fn run_tests_in_parallel() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let task_dir = exam_info.tasks_dir().join("task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(
+            task_dir.join("task.toml"),
+            "[runner]\nlanguage = \"command\"\ntest_command = \"sleep 0.1; touch leftover; test -f ok\"\n\
+            source_file = \"{task}.sh\"\n",
+        )
+        .unwrap();
+        for student in 0..6 {
+            let dir = exam_info
+                .submissions_dir()
+                .join(format!("student_{}", student))
+                .join("task_1");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("task_1.sh"), "echo solution\n").unwrap();
+            if student % 2 == 0 {
+                std::fs::write(dir.join("ok"), "").unwrap();
             }
         }
+        let exam_tester = ExamTester::new(exam_info).with_jobs(4);
+        assert!(exam_tester.copy_submissions().unwrap().is_empty());
+
+        let problems = exam_tester.run_tests().unwrap();
+
+        assert!(problems.is_empty(), "{}", problems.to_text());
+
+        let table = exam_tester.result_table().unwrap();
+        let results: Vec<_> = table.rows.iter().map(|(_, results)| results[0]).collect();
+        assert_eq!(
+            results,
+            [
+                Some(TestResult::Success),
+                Some(TestResult::TestFailure),
+                Some(TestResult::Success),
+                Some(TestResult::TestFailure),
+                Some(TestResult::Success),
+                Some(TestResult::TestFailure),
+            ]
+        );
+        let solution = exam_tester
+            .exam_info()
+            .grading_dir()
+            .join("student_1/task_1/task_1.sh");
+        assert_eq!(
+            std::fs::read_to_string(&solution).unwrap(),
+            "echo solution\n\n# BEWERTUNG: \n# TESTS: Test-Fehlschlag\n"
+        );
+        assert!(!solution.with_file_name("leftover").exists());
+    }
+
+    /// The kept version of a test modified by a student depends on the file precedence only.
+    #[test]
+    fn file_precedence_independent_of_order() {
+        for precedence in [FilePrecedence::Submission, FilePrecedence::Grading] {
+            for tests_first in [false, true] {
+                let exam_dir = tempfile::tempdir().unwrap();
+                let mut exam_info = ExamInfo::new(exam_dir.path());
+                exam_info.set_grading_options(GradingOptions {
+                    file_precedence: precedence,
+                    ..Default::default()
+                });
+                let task_dir = exam_info.tasks_dir().join("task_1");
+                let submission_dir = exam_info.submissions_dir().join("student_1/task_1");
+                std::fs::create_dir_all(&task_dir).unwrap();
+                std::fs::create_dir_all(&submission_dir).unwrap();
+                std::fs::write(task_dir.join("task_1_test.go"), "original").unwrap();
+                std::fs::write(submission_dir.join("task_1.go"), "solution").unwrap();
+                std::fs::write(submission_dir.join("task_1_test.go"), "modified").unwrap();
+                let exam_tester = ExamTester::new(exam_info);
+
+                let problems = if tests_first {
+                    std::fs::create_dir_all(
+                        exam_tester.exam_info().grading_dir().join("student_1"),
+                    )
+                    .unwrap();
+                    let mut problems = exam_tester.copy_tests().unwrap();
+                    problems.extend(exam_tester.copy_submissions().unwrap());
+                    problems
+                } else {
+                    let mut problems = exam_tester.copy_submissions().unwrap();
+                    problems.extend(exam_tester.copy_tests().unwrap());
+                    problems
+                };
+
+                assert!(problems.is_empty(), "{}", problems.to_text());
+                let test_file = exam_tester
+                    .exam_info()
+                    .grading_dir()
+                    .join("student_1/task_1/task_1_test.go");
+                let expected = match precedence {
+                    FilePrecedence::Grading => "original",
+                    _ => "modified",
+                };
+                assert_eq!(std::fs::read_to_string(test_file).unwrap(), expected);
+                let log = std::fs::read_to_string(exam_tester.exam_info().conflict_log()).unwrap();
+                assert_eq!(log.lines().count(), 1, "{}", log);
+                assert!(log.contains(r#""file":"task_1/task_1_test.go""#), "{}", log);
+            }
+        }
+    }
+
+    /// Copying the tests again replaces older copies of the task's files, e.g. a fixed grading test.
+    #[test]
+    fn copy_tests_replaces_older_task_files() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let task_dir = exam_info.tasks_dir().join("task_1");
+        let submission_dir = exam_info.submissions_dir().join("student_1/task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::create_dir_all(&submission_dir).unwrap();
+        std::fs::write(task_dir.join("task_1_grading_test.go"), "broken").unwrap();
+        std::fs::write(submission_dir.join("task_1.go"), "solution").unwrap();
+        let exam_tester = ExamTester::new(exam_info);
+        exam_tester.copy_submissions().unwrap();
+        exam_tester.copy_tests().unwrap();
+
+        std::fs::write(task_dir.join("task_1_grading_test.go"), "fixed").unwrap();
+        let problems = exam_tester.copy_tests().unwrap();
+
+        assert!(problems.is_empty(), "{}", problems.to_text());
+        let test_file = exam_tester
+            .exam_info()
+            .grading_dir()
+            .join("student_1/task_1/task_1_grading_test.go");
+        assert_eq!(std::fs::read_to_string(test_file).unwrap(), "fixed");
+        assert!(!exam_tester.exam_info().conflict_log().exists());
+    }
+
+    /// Conflicts are reported as problems if no precedence is given.
+    #[test]
+    fn file_precedence_error() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let mut exam_info = ExamInfo::new(exam_dir.path());
+        exam_info.set_grading_options(GradingOptions {
+            file_precedence: FilePrecedence::Error,
+            ..Default::default()
+        });
+        let task_dir = exam_info.tasks_dir().join("task_1");
+        let submission_dir = exam_info.submissions_dir().join("student_1/task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::create_dir_all(&submission_dir).unwrap();
+        std::fs::write(task_dir.join("task_1_test.go"), "original").unwrap();
+        std::fs::write(submission_dir.join("task_1.go"), "solution\n").unwrap();
+        std::fs::write(submission_dir.join("task_1_test.go"), "modified").unwrap();
+        let exam_tester = ExamTester::new(exam_info);
+        exam_tester.copy_submissions().unwrap();
+
+        let problems = exam_tester.copy_tests().unwrap();
+
+        assert_eq!(problems.problems().len(), 1);
+        assert!(matches!(
+            &problems.problems()[0],
+            Error::Student { student, source }
+                if student == "student_1" && matches!(**source, Error::FileConflict { .. })
+        ));
+
+        // Annotated files in the grading directory are kept without a problem.
+        let solution_file = exam_tester
+            .exam_info()
+            .grading_dir()
+            .join("student_1/task_1/task_1.go");
+        std::fs::write(&solution_file, "solution\n// annotated\n").unwrap();
+
+        let problems = exam_tester.copy_submissions().unwrap();
+
+        assert!(problems.is_empty(), "{}", problems.to_text());
+        assert_eq!(
+            std::fs::read_to_string(solution_file).unwrap(),
+            "solution\n// annotated\n"
+        );
+    }
+
+    /// Failing, flaky and slow tests are found by the verification.
+    /// Failing and flaky tests stop the test runs on the submissions.
+    #[cfg(unix)]
+    #[test]
+    fn verify_reference_solutions() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let counter = exam_dir.path().join("counter");
+        let tasks = [
+            ("task_1", "test -f solution".to_string()),
+            ("task_2", "test -f missing".to_string()),
+            (
+                "task_3",
+                format!(
+                    "n=$(cat {0} 2>/dev/null || echo 0); echo $((n + 1)) > {0}; test $((n % 2)) -eq 0",
+                    counter.display()
+                ),
+            ),
+            ("task_4", "sleep 0.6".to_string()),
+        ];
+        for (task, test_command) in &tasks {
+            let task_dir = exam_info.tasks_dir().join(task);
+            std::fs::create_dir_all(&task_dir).unwrap();
+            std::fs::write(task_dir.join("solution"), "").unwrap();
+            std::fs::write(
+                task_dir.join("task.toml"),
+                format!(
+                    "timeout = 1\n[runner]\nlanguage = \"command\"\ntest_command = {:?}\n\
+                    source_file = \"solution\"\n",
+                    test_command
+                ),
+            )
+            .unwrap();
+        }
+        std::fs::create_dir_all(exam_info.submissions_dir().join("student_1")).unwrap();
+        let exam_tester = ExamTester::new(exam_info).with_verification(true);
+
+        let report = exam_tester.verify().unwrap();
+
+        assert_eq!(report.tasks, ["task_1", "task_2", "task_3", "task_4"]);
+        assert_eq!(report.tasks_with_issues(), ["task_2", "task_3", "task_4"]);
+        let kinds: Vec<&IssueKind> = report.issues.iter().map(|issue| &issue.kind).collect();
+        assert!(matches!(
+            kinds[..],
+            [
+                IssueKind::Failed {
+                    result: TestResult::TestFailure,
+                    ..
+                },
+                IssueKind::Flaky { .. },
+                IssueKind::NearTimeout { .. },
+            ]
+        ));
+        assert!(!report.passed());
+        assert_eq!(report.failed_tasks(), ["task_2", "task_3"]);
+        assert!(matches!(
+            exam_tester.run_tests(),
+            Err(Error::Verification { tasks }) if tasks == ["task_2", "task_3"]
+        ));
+        assert_eq!(exam_tester.result_table().unwrap().tested_count(), (0, 4));
+    }
+
+    /// The language of a task is set in its configuration or detected from its files.
+    #[test]
+    fn task_language() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let tasks_dir = exam_info.tasks_dir();
+        std::fs::create_dir_all(tasks_dir.join("task_1")).unwrap();
+        std::fs::write(tasks_dir.join("task_1/task_1.go"), "").unwrap();
+        std::fs::create_dir_all(tasks_dir.join("task_2")).unwrap();
+        std::fs::write(
+            tasks_dir.join("task_2/task.toml"),
+            "[runner]\nlanguage = \"python\"\n",
+        )
+        .unwrap();
+        let exam_tester = ExamTester::new(exam_info);
+
+        assert_eq!(exam_tester.task_language("task_1"), Some(Language::Go));
+        assert_eq!(exam_tester.task_language("task_2"), Some(Language::Python));
+        assert_eq!(exam_tester.task_language("task_3"), None);
+    }
+
+    /// Tasks that were not handed in or lack the source file are not tested.
+    #[cfg(unix)]
+    #[test]
+    fn run_tests_not_submitted() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let task_dir = exam_info.tasks_dir().join("task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(
+            task_dir.join("task.toml"),
+            "[runner]\nlanguage = \"command\"\ntest_command = \"true\"\n\
+            test_patterns = [\"*.bats\"]\nsource_file = \"{task}.sh\"\n",
+        )
+        .unwrap();
+        std::fs::write(task_dir.join("task_1.bats"), "").unwrap();
+        let submissions_dir = exam_info.submissions_dir();
+        std::fs::create_dir_all(submissions_dir.join("student_1/other_task")).unwrap();
+        std::fs::create_dir_all(submissions_dir.join("student_2/task_1")).unwrap();
+        std::fs::write(submissions_dir.join("student_2/task_1/helper"), "").unwrap();
+        let exam_tester = ExamTester::new(exam_info);
+        exam_tester.copy_submissions().unwrap();
+        exam_tester.copy_tests().unwrap();
+
+        let problems = exam_tester.run_tests().unwrap();
+
+        assert!(problems.is_empty(), "{}", problems.to_text());
+        let table = exam_tester.result_table().unwrap();
+        assert_eq!(table.rows[0].1, [Some(TestResult::NotSubmitted)]);
+        assert_eq!(table.rows[1].1, [Some(TestResult::MissingSourceFile)]);
+    }
+
+    /// The test isolation of the Go batch only applies to submissions that are tested,
+    /// so a task that was not handed in is not reported as a problem.
+    #[test]
+    fn go_batch_isolation_skips_settled_runs() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let mut exam_info = ExamInfo::new(exam_dir.path());
+        exam_info.set_grading_options(GradingOptions {
+            test_isolation: TestIsolation::Exclude,
+            ..Default::default()
+        });
+        let task_dir = exam_info.tasks_dir().join("task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(task_dir.join("task_1_test.go"), "package task_1\n").unwrap();
+        let submissions_dir = exam_info.submissions_dir();
+        std::fs::create_dir_all(submissions_dir.join("student_1/task_1")).unwrap();
+        std::fs::write(
+            submissions_dir.join("student_1/task_1/task_1.go"),
+            "package task_1\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(submissions_dir.join("student_2/other_task")).unwrap();
+        let exam_tester = ExamTester::new(exam_info).with_go_batch(true);
+        exam_tester.copy_submissions().unwrap();
+
+        let problems = exam_tester.run_tests().unwrap();
+
+        // Without the go tool, the runs of student_1 are reported as problems.
+        assert!(
+            problems.problems().iter().all(|problem| !matches!(
+                problem,
+                Error::Task { student, .. } if student == "student_2"
+            )),
+            "{}",
+            problems.to_text()
+        );
+        let table = exam_tester.result_table().unwrap();
+        assert_eq!(table.rows[1].1, [Some(TestResult::NotSubmitted)]);
+    }
+
+    /// A submission that cannot be copied for the test run doesn't stop the test runs of the others.
+    #[cfg(unix)]
+    #[test]
+    fn run_tests_continues_after_problems() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let exam_info = ExamInfo::new(exam_dir.path());
+        let task_dir = exam_info.tasks_dir().join("task_1");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(
+            task_dir.join("task.toml"),
+            "[runner]\nlanguage = \"command\"\ntest_command = \"true\"\n\
+            source_file = \"{task}.sh\"\n",
+        )
+        .unwrap();
+        for student in ["student_1", "student_2"] {
+            let dir = exam_info.submissions_dir().join(student).join("task_1");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("task_1.sh"), "echo solution\n").unwrap();
+        }
+        let exam_tester = ExamTester::new(exam_info);
+        exam_tester.copy_submissions().unwrap();
+        std::os::unix::fs::symlink(
+            "missing",
+            exam_tester
+                .exam_info()
+                .grading_dir()
+                .join("student_1/task_1/dangling"),
+        )
+        .unwrap();
+
+        let problems = exam_tester.run_tests().unwrap();
+
+        assert_eq!(problems.problems().len(), 1);
+        assert!(
+            matches!(&problems.problems()[0], Error::Task { student, task, .. }
+                if student == "student_1" && task == "task_1"),
+            "{}",
+            problems.to_text()
+        );
+        let table = exam_tester.result_table().unwrap();
+        assert_eq!(table.rows[0].1, [None]);
+        assert_eq!(table.rows[1].1, [Some(TestResult::Success)]);
     }
 }
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Which version of a file is kept in the grading directory if a submission
+/// and a task contain different files of the same name, e.g. a test file
+/// delivered with the assignment and modified by the student.
+///
+/// The result doesn't depend on the order in which the submissions and the tests are copied.
+/// Other files already in the grading directory (e.g. solution files annotated
+/// in an earlier run) are never overwritten.
+/// Each conflict is logged, see [`FileConflict`].
+///
+/// In configuration files, the policy is set by the `file_precedence` key of the
+/// `[grading]` table, e.g. `file_precedence = "grading"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilePrecedence {
+    /// The file of the submission is kept.
+    #[default]
+    Submission,
+    /// The file of the task is kept.
+    Grading,
+    /// The file already in the grading directory is kept
+    /// and conflicts between a submission and a task are reported as problems.
+    Error,
+}
+
+/// Where a file in the grading directory comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileOrigin {
+    /// The student's submission.
+    Submission,
+    /// The task, e.g. a grading test.
+    Task,
+    /// An earlier version in the grading directory, e.g. an annotated solution file.
+    GradingDir,
+}
+
+/// A file that exists in different versions, logged when copying the submissions or tests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileConflict {
+    /// The student the file belongs to.
+    pub student: String,
+    /// The path of the file relative to the student's directory in the grading directory.
+    pub file: PathBuf,
+    /// The origin of the version in the grading directory after the copy.
+    pub kept: FileOrigin,
+    /// The origin of the version that was not copied or overwritten.
+    pub discarded: FileOrigin,
+}
+
+impl FilePrecedence {
+    /// Returns whether the file already in the grading directory
+    /// is overwritten by the file being copied.
+    pub fn overwrites(self, existing: FileOrigin, copied: FileOrigin) -> bool {
+        match (self, existing, copied) {
+            (_, FileOrigin::GradingDir, _) => false,
+            (FilePrecedence::Submission, FileOrigin::Task, FileOrigin::Submission) => true,
+            (FilePrecedence::Grading, _, FileOrigin::Task) => true,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for FilePrecedence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "submission" => Ok(FilePrecedence::Submission),
+            "grading" => Ok(FilePrecedence::Grading),
+            "error" => Ok(FilePrecedence::Error),
+            _ => Err(format!(
+                "unknown file precedence {:?}, expected submission, grading or error",
+                s
+            )),
+        }
+    }
+}
+
+impl FileConflict {
+    /// Creates the conflict between the existing and the copied version of a file,
+    /// resolved by overwriting the existing version or not.
+    pub fn new<S: Into<String>, P: Into<PathBuf>>(
+        student: S,
+        file: P,
+        existing: FileOrigin,
+        copied: FileOrigin,
+        overwritten: bool,
+    ) -> Self {
+        let (kept, discarded) = if overwritten {
+            (copied, existing)
+        } else {
+            (existing, copied)
+        };
+        Self {
+            student: student.into(),
+            file: file.into(),
+            kept,
+            discarded,
+        }
+    }
+}
+
+impl fmt::Display for FileOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let origin = match self {
+            FileOrigin::Submission => "submission",
+            FileOrigin::Task => "task",
+            FileOrigin::GradingDir => "grading directory",
+        };
+        write!(f, "{}", origin)
+    }
+}
+
+impl fmt::Display for FileConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}: kept version of {}, discarded version of {}",
+            self.student,
+            self.file.display(),
+            self.kept,
+            self.discarded
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overwrites() {
+        use FileOrigin::*;
+
+        assert!(FilePrecedence::Submission.overwrites(Task, Submission));
+        assert!(!FilePrecedence::Submission.overwrites(Submission, Task));
+        assert!(FilePrecedence::Grading.overwrites(Submission, Task));
+        assert!(!FilePrecedence::Grading.overwrites(Task, Submission));
+        assert!(!FilePrecedence::Error.overwrites(Submission, Task));
+        assert!(!FilePrecedence::Grading.overwrites(GradingDir, Task));
+    }
+}
@@ -0,0 +1,389 @@
+use crate::exam_tester::exam::{TestCase, TestOutcome, TestReport, TestResult};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// A single event as emitted by `go test -json` (see `go doc test2json`).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GoTestEvent {
+    action: String,
+    package: Option<String>,
+    import_path: Option<String>,
+    test: Option<String>,
+    elapsed: Option<f64>,
+    output: Option<String>,
+}
+
+impl TestReport {
+    /// Parses the event stream written by `go test -json` and returns the corresponding report.
+    ///
+    /// Lines that are not valid events (e.g. build errors written by older go versions)
+    /// are treated as output of the run as a whole.
+    /// Run failures (build failures, timeouts, crashes) are only detected
+    /// if the run did not pass, so output printed by the tested code
+    /// cannot turn a passing run into a failing one.
+    pub fn from_go_test_json(test_output: &str) -> Self {
+        let mut report = TestReport::default();
+        let mut running: HashMap<String, String> = HashMap::new();
+        let mut build_failed = false;
+
+        for line in test_output.lines() {
+            let event: GoTestEvent = match serde_json::from_str(line) {
+                Ok(event) => event,
+                Err(_) => {
+                    push_line(&mut report.output, line);
+                    continue;
+                }
+            };
+
+            match (event.action.as_str(), event.test) {
+                ("run", Some(test)) => {
+                    running.entry(test).or_default();
+                }
+                ("output", Some(test)) => {
+                    running
+                        .entry(test)
+                        .or_default()
+                        .push_str(event.output.as_deref().unwrap_or_default());
+                }
+                ("output" | "build-output", None) => {
+                    report
+                        .output
+                        .push_str(event.output.as_deref().unwrap_or_default());
+                }
+                ("build-fail", _) => build_failed = true,
+                ("pass" | "fail" | "skip", Some(test)) => {
+                    let output = running.remove(&test).unwrap_or_default();
+                    let outcome = go_test_outcome(&event.action, &output);
+                    report.cases.push(TestCase {
+                        name: test,
+                        outcome,
+                        elapsed: event.elapsed.map(Duration::from_secs_f64),
+                        output,
+                    });
+                }
+                ("pass" | "skip", None) => report.passed = true,
+                ("fail", None) => report.passed = false,
+                _ => {}
+            }
+        }
+
+        // Tests that never finished were interrupted, e.g. by a timeout or a crash.
+        let mut unfinished: Vec<_> = running.into_iter().collect();
+        unfinished.sort();
+        for (name, output) in unfinished {
+            let outcome = go_test_outcome("fail", &output);
+            report.cases.push(TestCase {
+                name,
+                outcome,
+                elapsed: None,
+                output,
+            });
+        }
+
+        if !report.passed {
+            report.failure = go_run_failure(&report, build_failed);
+        }
+        report
+    }
+}
+
+impl TestReport {
+    /// Parses the event stream written by `go test -json` for several packages (e.g. `./...`)
+    /// and returns a report for each package, keyed by the package's import path.
+    ///
+    /// Events are assigned to packages by their `Package` field,
+    /// build events by their `ImportPath` field.
+    /// Lines that are not valid events are assigned to the package
+    /// named by the last preceding `# <package>` header (as printed for build errors),
+    /// other lines are ignored.
+    /// Each package is parsed like the output of a single package,
+    /// so a failure of one package does not affect the reports of the others.
+    pub fn from_go_test_json_packages(test_output: &str) -> BTreeMap<String, TestReport> {
+        let mut outputs: BTreeMap<String, String> = BTreeMap::new();
+        let mut current_package: Option<String> = None;
+
+        for line in test_output.lines() {
+            let package = match serde_json::from_str::<GoTestEvent>(line) {
+                Ok(event) => event
+                    .package
+                    .or(event.import_path)
+                    .map(|path| package_of_import_path(&path).to_string()),
+                Err(_) => {
+                    if let Some(header) = line.strip_prefix("# ") {
+                        current_package = header
+                            .split_whitespace()
+                            .next()
+                            .map(|path| package_of_import_path(path).to_string());
+                    }
+                    current_package.clone()
+                }
+            };
+            if let Some(package) = package {
+                push_line(outputs.entry(package).or_default(), line);
+            }
+        }
+
+        outputs
+            .into_iter()
+            .map(|(package, output)| (package, Self::from_go_test_json(&output)))
+            .collect()
+    }
+}
+
+/// Returns the package an import path as used in build events belongs to,
+/// e.g. `m/task_1` for `m/task_1 [m/task_1.test]`, `m/task_1.test` or `m/task_1_test`.
+fn package_of_import_path(import_path: &str) -> &str {
+    let path = import_path
+        .split_once(" [")
+        .map_or(import_path, |(path, _)| path);
+    let path = path.strip_suffix(".test").unwrap_or(path);
+    path.strip_suffix("_test").unwrap_or(path)
+}
+
+/// Returns the outcome of a single test based on its final action and its output.
+fn go_test_outcome(action: &str, output: &str) -> TestOutcome {
+    match action {
+        "pass" => TestOutcome::Passed,
+        "skip" => TestOutcome::Skipped,
+        _ if has_line_starting_with(output, "runtime: goroutine stack exceeds") => {
+            TestOutcome::StackOverflow
+        }
+        _ if has_line_starting_with(output, "panic: ")
+            && !has_line_starting_with(output, "panic: test timed out after") =>
+        {
+            TestOutcome::Crashed
+        }
+        _ => TestOutcome::Failed,
+    }
+}
+
+/// Returns the failure of a failed run as a whole, if any.
+fn go_run_failure(report: &TestReport, build_failed: bool) -> Option<TestResult> {
+    let outputs = || {
+        std::iter::once(report.output.as_str())
+            .chain(report.cases.iter().map(|c| c.output.as_str()))
+    };
+    let any_line_starts_with = |prefix: &str| outputs().any(|o| has_line_starting_with(o, prefix));
+
+    if build_failed
+        || report.output.contains("[build failed]")
+        || report.output.contains("[setup failed]")
+    {
+        Some(TestResult::BuildFailure)
+    } else if any_line_starts_with("panic: test timed out after") {
+        Some(TestResult::Timeout)
+    } else if any_line_starts_with("runtime: goroutine stack exceeds") {
+        Some(TestResult::StackOverflow)
+    } else if has_line_starting_with(&report.output, "panic: ")
+        || has_line_starting_with(&report.output, "fatal error: ")
+    {
+        Some(TestResult::Crash)
+    } else {
+        None
+    }
+}
+
+fn has_line_starting_with(output: &str, prefix: &str) -> bool {
+    output.lines().any(|line| line.starts_with(prefix))
+}
+
+fn push_line(output: &mut String, line: &str) {
+    output.push_str(line);
+    output.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(events: &[&str]) -> String {
+        events.join("\n")
+    }
+
+    #[test]
+    fn success() {
+        let output = events(&[
+            r#"{"Action":"start","Package":"go-exam/task_1"}"#,
+            r#"{"Action":"run","Package":"go-exam/task_1","Test":"ExampleNumberList"}"#,
+            r#"{"Action":"output","Package":"go-exam/task_1","Test":"ExampleNumberList","Output":"=== RUN   ExampleNumberList\n"}"#,
+            r#"{"Action":"output","Package":"go-exam/task_1","Test":"ExampleNumberList","Output":"--- PASS: ExampleNumberList (0.00s)\n"}"#,
+            r#"{"Action":"pass","Package":"go-exam/task_1","Test":"ExampleNumberList","Elapsed":0.25}"#,
+            r#"{"Action":"output","Package":"go-exam/task_1","Output":"PASS\n"}"#,
+            r#"{"Action":"pass","Package":"go-exam/task_1","Elapsed":0.003}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert!(report.passed);
+        assert_eq!(report.result(), TestResult::Success);
+        assert_eq!(report.cases.len(), 1);
+        let case = report.case("ExampleNumberList").unwrap();
+        assert_eq!(case.outcome, TestOutcome::Passed);
+        assert_eq!(case.elapsed, Some(Duration::from_millis(250)));
+        assert!(case.output.contains("--- PASS: ExampleNumberList"));
+    }
+
+    #[test]
+    fn single_failing_example() {
+        let output = events(&[
+            r#"{"Action":"run","Test":"ExampleA"}"#,
+            r#"{"Action":"pass","Test":"ExampleA","Elapsed":0}"#,
+            r#"{"Action":"run","Test":"ExampleB"}"#,
+            r#"{"Action":"output","Test":"ExampleB","Output":"--- FAIL: ExampleB (0.00s)\n"}"#,
+            r#"{"Action":"fail","Test":"ExampleB","Elapsed":0}"#,
+            r#"{"Action":"output","Output":"FAIL\n"}"#,
+            r#"{"Action":"fail","Elapsed":0.003}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert!(!report.passed);
+        assert_eq!(report.result(), TestResult::TestFailure);
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Passed),
+            vec!["ExampleA"]
+        );
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["ExampleB"]
+        );
+    }
+
+    /// Output printed by the tested code must not influence a passing run.
+    #[test]
+    fn printed_fail_and_panic_in_passing_run() {
+        let output = events(&[
+            r#"{"Action":"run","Test":"TestA"}"#,
+            r#"{"Action":"output","Test":"TestA","Output":"FAIL\n"}"#,
+            r#"{"Action":"output","Test":"TestA","Output":"panic: not really\n"}"#,
+            r#"{"Action":"pass","Test":"TestA","Elapsed":0}"#,
+            r#"{"Action":"pass","Elapsed":0.003}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert_eq!(report.result(), TestResult::Success);
+    }
+
+    #[test]
+    fn build_failure_old_format() {
+        let output = events(&[
+            "# go-exam/task_3",
+            "./task_3.go:5:2: undefined: x",
+            r#"{"Action":"start","Package":"go-exam/task_3"}"#,
+            r#"{"Action":"output","Package":"go-exam/task_3","Output":"FAIL\tgo-exam/task_3 [build failed]\n"}"#,
+            r#"{"Action":"fail","Package":"go-exam/task_3","Elapsed":0}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+        assert!(report.output.contains("undefined: x"));
+    }
+
+    #[test]
+    fn build_failure_build_events() {
+        let output = events(&[
+            r#"{"ImportPath":"go-exam/task_3 [go-exam/task_3.test]","Action":"build-output","Output":"./task_3.go:5:2: undefined: x\n"}"#,
+            r#"{"ImportPath":"go-exam/task_3 [go-exam/task_3.test]","Action":"build-fail"}"#,
+            r#"{"Action":"start","Package":"go-exam/task_3"}"#,
+            r#"{"Action":"fail","Package":"go-exam/task_3","Elapsed":0,"FailedBuild":"go-exam/task_3 [go-exam/task_3.test]"}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+        assert!(report.output.contains("undefined: x"));
+    }
+
+    #[test]
+    fn packages() {
+        let output = events(&[
+            r#"{"ImportPath":"m/s1/task_3 [m/s1/task_3.test]","Action":"build-output","Output":"./task_3.go:5:2: undefined: x\n"}"#,
+            r#"{"ImportPath":"m/s1/task_3 [m/s1/task_3.test]","Action":"build-fail"}"#,
+            r#"{"Action":"start","Package":"m/s1/task_1"}"#,
+            r#"{"Action":"run","Package":"m/s1/task_1","Test":"TestA"}"#,
+            r#"{"Action":"start","Package":"m/s1/task_3"}"#,
+            r#"{"Action":"fail","Package":"m/s1/task_3","Elapsed":0,"FailedBuild":"m/s1/task_3 [m/s1/task_3.test]"}"#,
+            r#"{"Action":"pass","Package":"m/s1/task_1","Test":"TestA","Elapsed":0}"#,
+            r#"{"Action":"pass","Package":"m/s1/task_1","Elapsed":0.01}"#,
+        ]);
+        let reports = TestReport::from_go_test_json_packages(&output);
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports["m/s1/task_1"].result(), TestResult::Success);
+        assert_eq!(reports["m/s1/task_1"].cases.len(), 1);
+        assert_eq!(reports["m/s1/task_3"].result(), TestResult::BuildFailure);
+        assert!(reports["m/s1/task_3"].output.contains("undefined: x"));
+    }
+
+    #[test]
+    fn packages_old_build_output() {
+        let output = events(&[
+            "# m/s2/task_1 [m/s2/task_1.test]",
+            "./task_1.go:3:1: syntax error",
+            r#"{"Action":"output","Package":"m/s2/task_1","Output":"FAIL\tm/s2/task_1 [build failed]\n"}"#,
+            r#"{"Action":"fail","Package":"m/s2/task_1","Elapsed":0}"#,
+            r#"{"Action":"pass","Package":"m/s1/task_1","Elapsed":0}"#,
+        ]);
+        let reports = TestReport::from_go_test_json_packages(&output);
+
+        assert_eq!(reports["m/s2/task_1"].result(), TestResult::BuildFailure);
+        assert!(reports["m/s2/task_1"].output.contains("syntax error"));
+        assert_eq!(reports["m/s1/task_1"].result(), TestResult::Success);
+    }
+
+    #[test]
+    fn import_paths() {
+        assert_eq!(package_of_import_path("m/t [m/t.test]"), "m/t");
+        assert_eq!(package_of_import_path("m/t.test"), "m/t");
+        assert_eq!(package_of_import_path("m/t_test [m/t.test]"), "m/t");
+        assert_eq!(package_of_import_path("m/t"), "m/t");
+    }
+
+    #[test]
+    fn crash() {
+        let output = events(&[
+            r#"{"Action":"run","Test":"ExampleA"}"#,
+            r#"{"Action":"output","Test":"ExampleA","Output":"panic: runtime error: makeslice: len out of range\n"}"#,
+            r#"{"Action":"fail","Test":"ExampleA","Elapsed":0}"#,
+            r#"{"Action":"output","Output":"FAIL\tgo-exam/task_1\t0.123s\n"}"#,
+            r#"{"Action":"fail","Elapsed":0.123}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Crashed),
+            vec!["ExampleA"]
+        );
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let output = events(&[
+            r#"{"Action":"run","Test":"TestRec"}"#,
+            r#"{"Action":"output","Test":"TestRec","Output":"runtime: goroutine stack exceeds 1000000000-byte limit\n"}"#,
+            r#"{"Action":"output","Test":"TestRec","Output":"fatal error: stack overflow\n"}"#,
+            r#"{"Action":"fail","Test":"TestRec","Elapsed":1.5}"#,
+            r#"{"Action":"fail","Elapsed":1.6}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert_eq!(report.result(), TestResult::StackOverflow);
+    }
+
+    #[test]
+    fn timeout_unfinished_test() {
+        let output = events(&[
+            r#"{"Action":"run","Test":"TestLoop"}"#,
+            r#"{"Action":"output","Test":"TestLoop","Output":"=== RUN   TestLoop\n"}"#,
+            r#"{"Action":"output","Output":"panic: test timed out after 5s\n"}"#,
+            r#"{"Action":"fail","Elapsed":5.01}"#,
+        ]);
+        let report = TestReport::from_go_test_json(&output);
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["TestLoop"]
+        );
+        assert_eq!(report.result(), TestResult::Timeout);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::exam_tester::exam::{TestCase, TestOutcome, TestReport};
+use crate::Error;
+use std::time::Duration;
+
+/// A test case as reported in a JUnit XML report.
+///
+/// JUnit XML is written by many test frameworks (e.g. pytest, Catch2, JUnit),
+/// which differ in how they report problems.
+/// The cases can be mapped to the shared result model via [`TestReport::from_junit_cases`],
+/// using a framework specific classification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JUnitCase {
+    /// The name of the test case.
+    pub name: String,
+    /// The class (or module/file) the test case belongs to, may be empty.
+    pub classname: String,
+    /// The time the test took, if reported.
+    pub time: Option<Duration>,
+    /// The result of the test case.
+    pub result: JUnitResult,
+    /// Everything the test wrote to stdout and stderr.
+    pub output: String,
+}
+
+/// Result of a test case in a JUnit XML report.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JUnitResult {
+    /// The test passed, i.e. neither a failure nor an error was reported.
+    Passed,
+    /// The test was skipped.
+    Skipped,
+    /// The test failed, typically because of a failed assertion.
+    Failure(JUnitProblem),
+    /// The test failed with an unexpected error, typically an uncaught exception.
+    Error(JUnitProblem),
+}
+
+/// Details of a failure or error in a JUnit XML report.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JUnitProblem {
+    /// The `type` attribute, typically the exception type.
+    pub type_name: Option<String>,
+    /// The `message` attribute.
+    pub message: Option<String>,
+    /// The text content, typically a stack trace.
+    pub text: String,
+}
+
+impl JUnitCase {
+    /// Parses all test cases from a JUnit XML report.
+    /// Test cases are collected from all test suites, in document order.
+    pub fn parse_all(xml: &str) -> Result<Vec<JUnitCase>, Error> {
+        let document = roxmltree::Document::parse(xml).map_err(|e| Error::Parse {
+            format: "JUnit XML",
+            path: None,
+            message: e.to_string(),
+        })?;
+
+        let cases = document
+            .descendants()
+            .filter(|node| node.has_tag_name("testcase"))
+            .map(|node| {
+                let mut result = JUnitResult::Passed;
+                let mut output = String::new();
+                for child in node.children().filter(|child| child.is_element()) {
+                    match child.tag_name().name() {
+                        "failure" => result = JUnitResult::Failure(JUnitProblem::from_node(child)),
+                        "error" => result = JUnitResult::Error(JUnitProblem::from_node(child)),
+                        "skipped" => result = JUnitResult::Skipped,
+                        "system-out" | "system-err" => output.push_str(&node_text(child)),
+                        _ => {}
+                    }
+                }
+
+                JUnitCase {
+                    name: node.attribute("name").unwrap_or_default().to_string(),
+                    classname: node.attribute("classname").unwrap_or_default().to_string(),
+                    time: node
+                        .attribute("time")
+                        .and_then(|time| time.parse::<f64>().ok())
+                        .filter(|time| time.is_finite() && *time >= 0.0)
+                        .map(Duration::from_secs_f64),
+                    result,
+                    output,
+                }
+            })
+            .collect();
+        Ok(cases)
+    }
+
+    /// Returns the qualified name of the test case,
+    /// i.e. the name prefixed by the class name if there is one.
+    pub fn qualified_name(&self) -> String {
+        if self.classname.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.classname, self.name)
+        }
+    }
+
+    /// Returns the problem reported for the test case, if any.
+    pub fn problem(&self) -> Option<&JUnitProblem> {
+        match &self.result {
+            JUnitResult::Failure(problem) | JUnitResult::Error(problem) => Some(problem),
+            JUnitResult::Passed | JUnitResult::Skipped => None,
+        }
+    }
+
+    /// Returns the outcome following the common JUnit convention:
+    /// failures are failed assertions, errors are crashes.
+    pub fn default_outcome(&self) -> TestOutcome {
+        match self.result {
+            JUnitResult::Passed => TestOutcome::Passed,
+            JUnitResult::Skipped => TestOutcome::Skipped,
+            JUnitResult::Failure(_) => TestOutcome::Failed,
+            JUnitResult::Error(_) => TestOutcome::Crashed,
+        }
+    }
+}
+
+impl JUnitProblem {
+    fn from_node(node: roxmltree::Node) -> Self {
+        Self {
+            type_name: node.attribute("type").map(str::to_string),
+            message: node.attribute("message").map(str::to_string),
+            text: node_text(node),
+        }
+    }
+
+    /// Checks whether the type, message or text of the problem contain the given string.
+    pub fn mentions(&self, pattern: &str) -> bool {
+        self.type_name
+            .as_deref()
+            .is_some_and(|t| t.contains(pattern))
+            || self.message.as_deref().is_some_and(|m| m.contains(pattern))
+            || self.text.contains(pattern)
+    }
+}
+
+impl TestReport {
+    /// Parses a JUnit XML report and returns the corresponding report,
+    /// classifying failures as failed tests and errors as crashes.
+    /// The run passed if no test failed.
+    pub fn from_junit_xml(xml: &str) -> Result<Self, Error> {
+        let cases = JUnitCase::parse_all(xml)?;
+        Ok(Self::from_junit_cases(&cases, JUnitCase::default_outcome))
+    }
+
+    /// Creates a report from the test cases of a JUnit XML report,
+    /// using the given function to determine the outcome of each test case.
+    /// The run passed if no test failed.
+    pub fn from_junit_cases<F>(cases: &[JUnitCase], classify: F) -> Self
+    where
+        F: Fn(&JUnitCase) -> TestOutcome,
+    {
+        let cases: Vec<TestCase> = cases
+            .iter()
+            .map(|case| {
+                let mut output = String::new();
+                if let Some(problem) = case.problem() {
+                    if let Some(message) = &problem.message {
+                        output.push_str(message);
+                        output.push('\n');
+                    }
+                    output.push_str(&problem.text);
+                }
+                output.push_str(&case.output);
+
+                TestCase {
+                    name: case.qualified_name(),
+                    outcome: classify(case),
+                    elapsed: case.time,
+                    output,
+                }
+            })
+            .collect();
+
+        let passed = cases
+            .iter()
+            .all(|case| matches!(case.outcome, TestOutcome::Passed | TestOutcome::Skipped));
+        Self {
+            cases,
+            passed,
+            ..Default::default()
+        }
+    }
+}
+
+fn node_text(node: roxmltree::Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exam_tester::exam::TestResult;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="suite" tests="4" failures="1" errors="1" skipped="1">
+    <testcase classname="TaskTest" name="passes" time="0.012"/>
+    <testcase classname="TaskTest" name="fails" time="0.5">
+      <failure message="expected: &lt;2&gt; but was: &lt;1&gt;" type="AssertionFailedError">trace</failure>
+      <system-out>printed</system-out>
+    </testcase>
+    <testcase classname="TaskTest" name="errors">
+      <error type="java.lang.NullPointerException">npe trace</error>
+    </testcase>
+    <testcase classname="TaskTest" name="skipped"><skipped/></testcase>
+  </testsuite>
+</testsuites>"#;
+
+    #[test]
+    fn parse_all_cases() {
+        let cases = JUnitCase::parse_all(REPORT).unwrap();
+
+        assert_eq!(cases.len(), 4);
+        assert_eq!(cases[0].result, JUnitResult::Passed);
+        assert_eq!(cases[0].time, Some(Duration::from_millis(12)));
+        assert_eq!(cases[1].qualified_name(), "TaskTest.fails");
+        assert_eq!(cases[1].output, "printed");
+        assert_eq!(
+            cases[1].problem().unwrap().message.as_deref(),
+            Some("expected: <2> but was: <1>")
+        );
+        assert!(cases[2].problem().unwrap().mentions("NullPointerException"));
+        assert_eq!(cases[3].result, JUnitResult::Skipped);
+    }
+
+    #[test]
+    fn report_default_classification() {
+        let report = TestReport::from_junit_xml(REPORT).unwrap();
+
+        assert!(!report.passed);
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["TaskTest.fails"]
+        );
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Crashed),
+            vec!["TaskTest.errors"]
+        );
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn report_passed() {
+        let xml = r#"<testsuite><testcase name="a"/><testcase name="b"><skipped/></testcase></testsuite>"#;
+        let report = TestReport::from_junit_xml(xml).unwrap();
+
+        assert!(report.passed);
+        assert_eq!(report.result(), TestResult::Success);
+    }
+
+    #[test]
+    fn invalid_xml() {
+        assert!(TestReport::from_junit_xml("<testsuite>").is_err());
+    }
+}
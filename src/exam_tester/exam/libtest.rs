@@ -0,0 +1,370 @@
+use crate::exam_tester::exam::{TestCase, TestOutcome, TestReport, TestResult};
+
+impl TestReport {
+    /// Parses the output of the Rust test harness (libtest) as printed by `cargo test`
+    /// and returns the corresponding report.
+    ///
+    /// The output may contain the results of several test binaries
+    /// (unit tests, integration tests, doc tests) as well as cargo's own messages.
+    /// As libtest doesn't report the duration of single tests on stable Rust,
+    /// no durations are recorded.
+    ///
+    /// Failing tests are reported as crashed if they panicked for a reason
+    /// other than a failed assertion, e.g. an index out of bounds or an `unwrap()` on `None`.
+    pub fn from_libtest_output(test_output: &str) -> Self {
+        let mut report = TestReport::default();
+        let mut any_result = false;
+        let mut any_failed_result = false;
+        let mut section: Option<(String, String)> = None;
+
+        for line in test_output.lines() {
+            if let Some(name) = failure_section_name(line) {
+                if let Some((name, output)) = section.take() {
+                    add_failure_output(&mut report, &name, output);
+                }
+                section = Some((name.to_string(), String::new()));
+                continue;
+            }
+            if line == "failures:" || line.starts_with("test result:") {
+                if let Some((name, output)) = section.take() {
+                    add_failure_output(&mut report, &name, output);
+                }
+            }
+            if let Some((_, output)) = section.as_mut() {
+                output.push_str(line);
+                output.push('\n');
+                continue;
+            }
+
+            if let Some((name, outcome)) = test_line(line) {
+                report.cases.push(TestCase {
+                    name: name.to_string(),
+                    outcome,
+                    elapsed: None,
+                    output: String::new(),
+                });
+            } else if let Some(name) = stack_overflow_thread(line) {
+                report.cases.push(TestCase {
+                    name: name.to_string(),
+                    outcome: TestOutcome::StackOverflow,
+                    elapsed: None,
+                    output: format!("{}\n", line),
+                });
+                report.failure = Some(TestResult::StackOverflow);
+            } else if let Some(summary) = line.strip_prefix("test result: ") {
+                any_result = true;
+                any_failed_result |= !summary.starts_with("ok.");
+            } else {
+                report.output.push_str(line);
+                report.output.push('\n');
+            }
+        }
+        if let Some((name, output)) = section.take() {
+            add_failure_output(&mut report, &name, output);
+        }
+
+        if is_build_failure(&report.output) {
+            report.failure = Some(TestResult::BuildFailure);
+        } else if report.failure.is_none() && is_abnormal_exit(&report.output) {
+            report.failure = Some(TestResult::Crash);
+        }
+        report.passed = any_result && !any_failed_result && report.failure.is_none();
+        report
+    }
+}
+
+/// Parses a line like `test tests::name ... ok` into the test name and outcome.
+fn test_line(line: &str) -> Option<(&str, TestOutcome)> {
+    let rest = line.strip_prefix("test ")?;
+    let (name, result) = rest.split_once(" ...")?;
+    let outcome = match result.trim() {
+        "ok" => TestOutcome::Passed,
+        "FAILED" => TestOutcome::Failed,
+        result if result.starts_with("ignored") => TestOutcome::Skipped,
+        _ => return None,
+    };
+    Some((name.trim(), outcome))
+}
+
+/// Parses a header like `---- tests::name stdout ----` into the test name.
+fn failure_section_name(line: &str) -> Option<&str> {
+    line.strip_prefix("---- ")?.strip_suffix(" stdout ----")
+}
+
+/// Parses a line like `thread 'tests::name' has overflowed its stack` into the test name.
+/// Newer Rust versions add the thread id after the name, e.g. `thread 'name' (1234) has ...`.
+fn stack_overflow_thread(line: &str) -> Option<&str> {
+    if !line.ends_with(" has overflowed its stack") {
+        return None;
+    }
+    thread_name(line)
+}
+
+/// Returns the thread name from a line starting with `thread 'name'`.
+fn thread_name(line: &str) -> Option<&str> {
+    let (name, _) = line.strip_prefix("thread '")?.split_once('\'')?;
+    Some(name)
+}
+
+/// Attaches the output of a failed test and reclassifies the test
+/// as crashed if it panicked for a reason other than a failed assertion.
+fn add_failure_output(report: &mut TestReport, name: &str, output: String) {
+    let Some(case) = report.cases.iter_mut().find(|case| case.name == name) else {
+        return;
+    };
+    if case.outcome == TestOutcome::Failed && is_non_assertion_panic(&output) {
+        case.outcome = TestOutcome::Crashed;
+    }
+    case.output = output;
+}
+
+/// Beginnings of panic messages of runtime errors, which are crashes
+/// even if they happen in the tests (e.g. `unwrap()` on a result of the submission).
+const RUNTIME_ERRORS: &[&str] = &[
+    "called `Option::unwrap()`",
+    "called `Result::unwrap()`",
+    "called `Result::unwrap_err()`",
+    "index out of bounds",
+    "range start index",
+    "range end index",
+    "slice index",
+    "byte index",
+    "attempt to ",
+    "capacity overflow",
+    "explicit panic",
+    "not implemented",
+    "not yet implemented",
+    "internal error: entered unreachable code",
+];
+
+/// Checks whether the captured output contains a panic that isn't caused by an assertion.
+/// Handles both the current format where the message follows the location on the next line
+/// and the older format `thread 'name' panicked at 'message', location`.
+fn is_non_assertion_panic(output: &str) -> bool {
+    let mut lines = output.lines();
+    while let Some(line) = lines.next() {
+        if thread_name(line).is_none() {
+            continue;
+        }
+        if let Some((_, rest)) = line.split_once(" panicked at ") {
+            let (message, location) = match rest.strip_prefix('\'') {
+                Some(rest) => rest.rsplit_once("', ").unwrap_or((rest, "")),
+                None => (lines.next().unwrap_or_default(), rest),
+            };
+            return !is_assertion(message, location);
+        }
+    }
+    false
+}
+
+/// Checks whether a panic with the given message and location is caused by an assertion.
+/// Besides the messages of `assert_eq!` and `assert!` without a message,
+/// panics in the integration tests (`tests/`) are assertions, e.g. `assert!(ok, "message")`,
+/// unless they are runtime errors.
+fn is_assertion(message: &str, location: &str) -> bool {
+    if message.contains("assertion") {
+        return true;
+    }
+    let in_tests = location.starts_with("tests/") || location.contains("/tests/");
+    in_tests
+        && !RUNTIME_ERRORS
+            .iter()
+            .any(|error| message.starts_with(error))
+}
+
+fn is_build_failure(output: &str) -> bool {
+    output
+        .lines()
+        .any(|line| line.starts_with("error: could not compile") || line.starts_with("error["))
+}
+
+fn is_abnormal_exit(output: &str) -> bool {
+    output
+        .lines()
+        .any(|line| line.contains("process didn't exit successfully") && line.contains("signal:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> String {
+        lines.join("\n")
+    }
+
+    #[test]
+    fn success() {
+        let output = lines(&[
+            "running 2 tests",
+            "test tests::a ... ok",
+            "test tests::b ... ignored",
+            "",
+            "test result: ok. 1 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out",
+            "",
+            "running 1 test",
+            "test grading ... ok",
+            "",
+            "test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert!(report.passed);
+        assert_eq!(report.result(), TestResult::Success);
+        assert_eq!(report.cases.len(), 3);
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Skipped),
+            vec!["tests::b"]
+        );
+    }
+
+    #[test]
+    fn assertion_failure() {
+        let output = lines(&[
+            "running 2 tests",
+            "test a ... ok",
+            "test b ... FAILED",
+            "",
+            "failures:",
+            "",
+            "---- b stdout ----",
+            "",
+            "thread 'b' panicked at tests/grading.rs:9:5:",
+            "assertion `left == right` failed",
+            "  left: 1",
+            " right: 2",
+            "",
+            "",
+            "failures:",
+            "    b",
+            "",
+            "test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert!(!report.passed);
+        assert_eq!(report.result(), TestResult::TestFailure);
+        assert!(report.case("b").unwrap().output.contains("left: 1"));
+    }
+
+    #[test]
+    fn panic_is_crash() {
+        let output = lines(&[
+            "running 1 test",
+            "test b ... FAILED",
+            "",
+            "failures:",
+            "",
+            "---- b stdout ----",
+            "thread 'b' (5347) panicked at src/lib.rs:3:5:",
+            "index out of bounds: the len is 3 but the index is 7",
+            "",
+            "failures:",
+            "    b",
+            "",
+            "test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert_eq!(report.tests_with_outcome(TestOutcome::Crashed), vec!["b"]);
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn assertion_with_message() {
+        let output = lines(&[
+            "running 2 tests",
+            "test a ... FAILED",
+            "test b ... FAILED",
+            "",
+            "failures:",
+            "",
+            "---- a stdout ----",
+            "thread 'a' panicked at tests/grading.rs:4:5:",
+            "sum of [1, 2] should be 3",
+            "",
+            "---- b stdout ----",
+            "thread 'b' panicked at tests/grading.rs:9:5:",
+            "called `Option::unwrap()` on a `None` value",
+            "",
+            "failures:",
+            "    a",
+            "    b",
+            "",
+            "test result: FAILED. 0 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert_eq!(report.tests_with_outcome(TestOutcome::Failed), vec!["a"]);
+        assert_eq!(report.tests_with_outcome(TestOutcome::Crashed), vec!["b"]);
+    }
+
+    #[test]
+    fn assertion_with_message_old_format() {
+        let output = lines(&[
+            "running 1 test",
+            "test a ... FAILED",
+            "",
+            "failures:",
+            "",
+            "---- a stdout ----",
+            "thread 'a' panicked at 'sum should be 3', tests/grading.rs:4:5",
+            "",
+            "failures:",
+            "    a",
+            "",
+            "test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let output = lines(&[
+            "running 2 tests",
+            "test other ... ok",
+            "test rec ... ",
+            "thread 'rec' (5334) has overflowed its stack",
+            "fatal runtime error: stack overflow, aborting",
+            "error: test failed, to rerun pass `--test grading`",
+            "",
+            "Caused by:",
+            "  process didn't exit successfully: `/t/grading-1234` (signal: 6, SIGABRT: process abort signal)",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert!(!report.passed);
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::StackOverflow),
+            vec!["rec"]
+        );
+        assert_eq!(report.result(), TestResult::StackOverflow);
+    }
+
+    #[test]
+    fn abnormal_exit_is_crash() {
+        let output = lines(&[
+            "running 1 test",
+            "error: test failed, to rerun pass `--lib`",
+            "  process didn't exit successfully: `/t/task-1234` (signal: 11, SIGSEGV: invalid memory reference)",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn build_failure() {
+        let output = lines(&[
+            "   Compiling task_1 v0.1.0 (/exam/bewertung/student_1/task_1)",
+            "error[E0308]: mismatched types",
+            " --> src/lib.rs:2:5",
+            "error: could not compile `task_1` (lib) due to 1 previous error",
+        ]);
+        let report = TestReport::from_libtest_output(&output);
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+        assert!(report.output.contains("mismatched types"));
+    }
+}
@@ -1,7 +1,34 @@
+mod assignment;
+mod exam_config;
 mod exam_info;
 mod exam_tester;
+mod file_precedence;
+mod go_test_json;
+mod junit_xml;
+mod libtest;
+mod problem_report;
+mod result_store;
+mod staging;
+mod tap;
+mod task_info;
+mod test_isolation;
+mod test_report;
 mod test_result;
+mod verification;
 
-pub use exam_info::ExamInfo;
+pub use assignment::{AssignmentBuilder, TemplateCheck, BEGIN_SOLUTION, END_SOLUTION};
+pub use exam_config::{DirectoriesConfig, ExamConfig, GradingOptions, MessageLanguage, TaskConfig};
+pub use exam_info::{ExamInfo, CONFIG_FILE, STATE_DIR};
 pub use exam_tester::ExamTester;
+pub use file_precedence::{FileConflict, FileOrigin, FilePrecedence};
+pub use junit_xml::{JUnitCase, JUnitProblem, JUnitResult};
+pub use problem_report::ProblemReport;
+pub use result_store::{ResultStore, ResultTable};
+pub use staging::StagedTask;
+pub use task_info::{TaskInfo, TASK_CONFIG_FILE};
+pub use test_isolation::{TestIsolation, QUARANTINE_DIR, RENAMED_TEST_SUFFIX};
+pub use test_report::{TestCase, TestOutcome, TestReport};
 pub use test_result::TestResult;
+pub use verification::{
+    IssueKind, VerificationIssue, VerificationReport, NEAR_TIMEOUT_FRACTION, VERIFICATION_RUNS,
+};
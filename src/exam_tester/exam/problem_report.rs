@@ -0,0 +1,82 @@
+use crate::Error;
+
+/// The problems of a grading step that need manual attention,
+/// e.g. an unreadable submission or a test run that could not be started.
+///
+/// The steps of [`ExamTester`](crate::exam_tester::exam::ExamTester) don't stop at
+/// such problems, they continue with the other students and tasks and collect the problems.
+/// Problems of single students or tasks carry the student and task
+/// (see [`Error::in_student`] and [`Error::in_task`]).
+#[derive(Debug, Default)]
+pub struct ProblemReport {
+    problems: Vec<Error>,
+}
+
+impl ProblemReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a problem to the report.
+    pub fn push(&mut self, problem: Error) {
+        self.problems.push(problem);
+    }
+
+    /// Adds the problems of the given report to this report.
+    pub fn extend(&mut self, other: ProblemReport) {
+        self.problems.extend(other.problems);
+    }
+
+    /// Returns whether no problems happened.
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Returns the problems in the order they happened.
+    pub fn problems(&self) -> &[Error] {
+        &self.problems
+    }
+
+    /// Returns the problems as text, one problem per line.
+    pub fn to_text(&self) -> String {
+        self.problems
+            .iter()
+            .map(|problem| format!("{}\n", problem))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn to_text() {
+        let mut report = ProblemReport::new();
+        report.push(
+            Error::io(
+                "read directory",
+                "abgaben/student_1",
+                io::Error::from(io::ErrorKind::PermissionDenied),
+            )
+            .in_student("student_1"),
+        );
+        let mut other = ProblemReport::new();
+        other.push(
+            Error::UnknownLanguage {
+                task: "task_2".to_string(),
+            }
+            .in_task("student_2", "task_2"),
+        );
+
+        report.extend(other);
+
+        assert_eq!(
+            report.to_text(),
+            "student_1: failed to read directory abgaben/student_1: permission denied\n\
+            student_2/task_2: unknown language of task task_2\n"
+        );
+    }
+}
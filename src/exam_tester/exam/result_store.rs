@@ -0,0 +1,238 @@
+use crate::exam_tester::exam::{MessageLanguage, TestReport, TestResult};
+use crate::Error;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Stores the reports of test runs as JSON files,
+/// one file per student and task (`<dir>/<student>/<task>.json`).
+///
+/// This allows reports and status information to be created
+/// without running the tests again.
+#[derive(Debug, Clone)]
+pub struct ResultStore {
+    dir: PathBuf,
+}
+
+impl ResultStore {
+    /// Creates a store keeping the results in the given directory.
+    /// The directory is created when the first result is saved.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory the results are stored in.
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    fn file(&self, student_name: &str, task_name: &str) -> PathBuf {
+        self.dir
+            .join(student_name)
+            .join(format!("{}.json", task_name))
+    }
+
+    /// Saves the report of the test run for the given student and task,
+    /// replacing a previously saved report.
+    pub fn save(
+        &self,
+        student_name: &str,
+        task_name: &str,
+        report: &TestReport,
+    ) -> Result<(), Error> {
+        let file = self.file(student_name, task_name);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::io("create directory", parent, e))?;
+        }
+        let json = serde_json::to_string_pretty(report)
+            .map_err(|e| Error::io("write", &file, io::Error::other(e)))?;
+        fs::write(&file, json).map_err(|e| Error::io("write", &file, e))
+    }
+
+    /// Loads the report saved for the given student and task.
+    /// Returns `None` if no report was saved, i.e. the task was not tested yet.
+    pub fn load(&self, student_name: &str, task_name: &str) -> Result<Option<TestReport>, Error> {
+        let file = self.file(student_name, task_name);
+        let json = match fs::read_to_string(&file) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::io("read", &file, e)),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| Error::Parse {
+                format: "test report",
+                path: Some(file),
+                message: e.to_string(),
+            })
+    }
+
+    /// Removes all saved reports.
+    pub fn clear(&self) -> Result<(), Error> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(Error::io("remove", &self.dir, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The results of all students (rows) for all tasks (columns).
+/// A result is `None` if the task was not tested for the student.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResultTable {
+    /// The task names, i.e. the column headers.
+    pub tasks: Vec<String>,
+    /// The student names with their results, in the order of the tasks.
+    pub rows: Vec<(String, Vec<Option<TestResult>>)>,
+}
+
+impl ResultTable {
+    /// Returns the number of tested and of all student/task combinations.
+    pub fn tested_count(&self) -> (usize, usize) {
+        let results = self.rows.iter().flat_map(|(_, results)| results);
+        let total = results.clone().count();
+        (results.filter(|result| result.is_some()).count(), total)
+    }
+
+    /// Formats the table as CSV with a header line.
+    /// Missing results are written as empty fields.
+    pub fn to_csv(&self, language: MessageLanguage) -> String {
+        self.lines(language, "")
+            .iter()
+            .map(|line| {
+                let fields: Vec<String> = line.iter().map(|field| csv_field(field)).collect();
+                fields.join(",") + "\n"
+            })
+            .collect()
+    }
+
+    /// Formats the table as text with aligned columns.
+    /// Missing results are shown as `-`.
+    pub fn to_text(&self, language: MessageLanguage) -> String {
+        let lines = self.lines(language, "-");
+        let columns = lines.first().map_or(0, Vec::len);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                lines
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut text = String::new();
+        for line in &lines {
+            let mut row = String::new();
+            for (field, width) in line.iter().zip(&widths) {
+                row.push_str(&format!("{:width$}  ", field, width = width));
+            }
+            text.push_str(row.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Returns the header line and the rows as fields.
+    fn lines(&self, language: MessageLanguage, missing: &str) -> Vec<Vec<String>> {
+        let header = std::iter::once(String::new())
+            .chain(self.tasks.iter().cloned())
+            .collect();
+        let rows = self.rows.iter().map(|(student_name, results)| {
+            std::iter::once(student_name.clone())
+                .chain(results.iter().map(|result| match result {
+                    Some(result) => result.to_string_in(language),
+                    None => missing.to_string(),
+                }))
+                .collect()
+        });
+        std::iter::once(header).chain(rows).collect()
+    }
+}
+
+/// Quotes a CSV field if necessary.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exam_tester::exam::{TestCase, TestOutcome};
+    use std::time::Duration;
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ResultStore::new(dir.path().join("results"));
+        let report = TestReport {
+            cases: vec![TestCase {
+                name: "TestA".to_string(),
+                outcome: TestOutcome::Failed,
+                elapsed: Some(Duration::from_millis(20)),
+                output: "--- FAIL: TestA\n".to_string(),
+            }],
+            passed: false,
+            failure: None,
+            output: String::new(),
+        };
+
+        assert_eq!(store.load("student_1", "task_1").unwrap(), None);
+        store.save("student_1", "task_1", &report).unwrap();
+        assert_eq!(store.load("student_1", "task_1").unwrap(), Some(report));
+
+        store.clear().unwrap();
+        assert_eq!(store.load("student_1", "task_1").unwrap(), None);
+        store.clear().unwrap();
+    }
+
+    fn table() -> ResultTable {
+        ResultTable {
+            tasks: vec!["task_1".to_string(), "task_2".to_string()],
+            rows: vec![
+                (
+                    "student_1".to_string(),
+                    vec![Some(TestResult::Success), Some(TestResult::BuildFailure)],
+                ),
+                ("student_2".to_string(), vec![Some(TestResult::Crash), None]),
+            ],
+        }
+    }
+
+    #[test]
+    fn tested_count() {
+        assert_eq!(table().tested_count(), (3, 4));
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            table().to_csv(MessageLanguage::En),
+            ",task_1,task_2\nstudent_1,Success,Build Failure\nstudent_2,Crash,\n"
+        );
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            table().to_text(MessageLanguage::De),
+            [
+                "           task_1   task_2",
+                "student_1  Ok       Build-Fehlschlag",
+                "student_2  Absturz  -",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
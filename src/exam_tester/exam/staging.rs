@@ -0,0 +1,164 @@
+use crate::exam_tester::exam::TestReport;
+use crate::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// A temporary working copy of a student's task directory for a single test run.
+///
+/// The tests run in the copy, so files written by the submission or the tests
+/// (e.g. build artifacts, files created by student code) never end up in the grading directory.
+/// The copy is removed when the staged task is dropped.
+///
+/// Files of the module the task belongs to (see [`Language::module_files`]),
+/// e.g. the `go.mod` of the exam, are copied as well,
+/// keeping the path of the task relative to the module's root directory.
+/// So e.g. the import path of a Go package doesn't change in the copy.
+///
+/// [`Language::module_files`]: crate::exam_tester::process::Language::module_files
+pub struct StagedTask {
+    root: TempDir,
+    /// The directory corresponding to the root of the copy, i.e. the module's root directory,
+    /// or else the parent of the task directory.
+    original_root: PathBuf,
+    task_dir: PathBuf,
+}
+
+impl StagedTask {
+    /// Copies the given task directory and module files to a new temporary directory.
+    /// The module files are expected to be in an ancestor of the task directory.
+    pub fn new(task_dir: &Path, module_files: &[PathBuf]) -> Result<Self, Error> {
+        let original_root = match module_files.first().and_then(|file| file.parent()) {
+            Some(module_dir) => module_dir,
+            None => task_dir.parent().unwrap_or(task_dir),
+        };
+        let relative_dir = task_dir.strip_prefix(original_root).map_err(|_| {
+            let message = format!(
+                "not inside the module directory {}",
+                original_root.display()
+            );
+            Error::io(
+                "copy",
+                task_dir,
+                io::Error::new(io::ErrorKind::InvalidInput, message),
+            )
+        })?;
+        let is_dir = fs::symlink_metadata(task_dir).is_ok_and(|metadata| metadata.is_dir());
+        if !is_dir {
+            return Err(Error::io(
+                "copy",
+                task_dir,
+                io::Error::new(io::ErrorKind::NotFound, "not a directory"),
+            ));
+        }
+
+        let root = tempfile::tempdir()
+            .map_err(|e| Error::io("create directory in", std::env::temp_dir(), e))?;
+        for file in module_files {
+            let Some(file_name) = file.file_name() else {
+                continue;
+            };
+            fs::copy(file, root.path().join(file_name)).map_err(|e| Error::io("copy", file, e))?;
+        }
+        let staged_task_dir = root.path().join(relative_dir);
+        crate::filesystem::copy_dir(task_dir, &staged_task_dir, &mut |_, _| false)?;
+
+        Ok(Self {
+            original_root: original_root.to_path_buf(),
+            task_dir: staged_task_dir,
+            root,
+        })
+    }
+
+    /// Returns the task directory in the copy, i.e. the directory to run the tests in.
+    pub fn task_dir(&self) -> &Path {
+        &self.task_dir
+    }
+
+    /// Replaces the paths of the copy in the outputs of the given report
+    /// (e.g. in compiler diagnostics) with the original paths.
+    pub fn restore_paths(&self, report: &mut TestReport) {
+        let staged = self.root.path().to_string_lossy().into_owned();
+        let original = self.original_root.to_string_lossy();
+        let outputs = std::iter::once(&mut report.output)
+            .chain(report.cases.iter_mut().map(|case| &mut case.output));
+        for output in outputs {
+            if output.contains(&staged) {
+                *output = output.replace(&staged, &original);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_go_package() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let task_dir = exam_dir.path().join("bewertung/student_1/task_1");
+        fs::create_dir_all(&task_dir).unwrap();
+        fs::write(task_dir.join("task_1.go"), "package task_1\n").unwrap();
+        fs::write(exam_dir.path().join("go.mod"), "module go-exam\n").unwrap();
+        fs::write(exam_dir.path().join("README.md"), "").unwrap();
+
+        let staged = StagedTask::new(&task_dir, &[exam_dir.path().join("go.mod")]).unwrap();
+        fs::write(staged.task_dir().join("leftover"), "").unwrap();
+
+        assert!(staged.task_dir().ends_with("bewertung/student_1/task_1"));
+        assert!(staged.task_dir().join("task_1.go").is_file());
+        let staged_root = staged.task_dir().ancestors().nth(3).unwrap();
+        assert!(staged_root.join("go.mod").is_file());
+        assert!(!staged_root.join("README.md").exists());
+        assert!(!task_dir.join("leftover").exists());
+
+        let staged_dir = staged.task_dir().to_path_buf();
+        drop(staged);
+        assert!(!staged_dir.exists());
+    }
+
+    /// Symbolic links are not followed, e.g. to files of the grader or other students.
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_rejected() {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let task_dir = exam_dir.path().join("student_1/task_1");
+        fs::create_dir_all(&task_dir).unwrap();
+        fs::write(exam_dir.path().join("secret"), "").unwrap();
+        std::os::unix::fs::symlink(exam_dir.path().join("secret"), task_dir.join("x")).unwrap();
+        std::os::unix::fs::symlink(&task_dir, task_dir.join("loop")).unwrap();
+
+        let error = StagedTask::new(&task_dir, &[]).err().unwrap();
+
+        assert!(
+            error
+                .to_string()
+                .contains("symbolic links are not supported"),
+            "{}",
+            error
+        );
+        assert!(StagedTask::new(&task_dir.join("loop"), &[]).is_err());
+    }
+
+    #[test]
+    fn restore_paths() {
+        let task_dir = tempfile::tempdir().unwrap();
+        let staged = StagedTask::new(task_dir.path(), &[]).unwrap();
+        let mut report = TestReport::build_failure(format!(
+            "{}/Main.java:3: error: ';' expected",
+            staged.task_dir().display()
+        ));
+
+        staged.restore_paths(&mut report);
+
+        assert_eq!(
+            report.output,
+            format!(
+                "{}/Main.java:3: error: ';' expected",
+                task_dir.path().display()
+            )
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use crate::exam_tester::exam::{TestCase, TestOutcome, TestReport, TestResult};
+use std::time::Duration;
+
+impl TestReport {
+    /// Parses output in the Test Anything Protocol (TAP, versions 13 and 14)
+    /// and returns the corresponding report.
+    ///
+    /// The following parts of the protocol are handled:
+    /// * `ok`/`not ok` test lines with an optional number and description.
+    /// * `# SKIP` and `# TODO` directives, which mark the test as skipped
+    ///   regardless of whether it is `ok` or `not ok`.
+    /// * YAML diagnostics blocks (`---` to `...`) following a test,
+    ///   which are added to the test's output. A `duration_ms` entry
+    ///   (as written by `node --test`) is used as the test's duration.
+    /// * Diagnostic lines (`# ...`) following a failed test, which are added to its output.
+    /// * `Bail out!`, which aborts the run and is reported as a crash.
+    /// * The plan (e.g. `1..3`). The run only passes if the planned number of tests was run.
+    ///
+    /// Indented lines (e.g. TAP 14 subtests) are attributed to the enclosing test,
+    /// only top level tests are reported as test cases.
+    /// Any other lines are treated as output of the run as a whole.
+    pub fn from_tap(test_output: &str) -> Self {
+        let mut report = TestReport::default();
+        let mut planned: Option<usize> = None;
+        let mut in_yaml = false;
+        let mut pending_output = String::new();
+        let mut bailed_out = false;
+
+        for line in test_output.lines() {
+            if in_yaml {
+                in_yaml = line.trim() != "...";
+                if let Some(case) = report.cases.last_mut() {
+                    if let Some(duration) = yaml_duration(line) {
+                        case.elapsed = Some(duration);
+                    }
+                    push_line(&mut case.output, line);
+                }
+                continue;
+            }
+            if line.trim_end() == "  ---" && !report.cases.is_empty() {
+                in_yaml = true;
+                continue;
+            }
+
+            if let Some(test) = tap_test_line(line) {
+                let mut output = std::mem::take(&mut pending_output);
+                output.push_str(line);
+                output.push('\n');
+                report.cases.push(TestCase {
+                    name: test.description.to_string(),
+                    outcome: test.outcome,
+                    elapsed: None,
+                    output,
+                });
+            } else if let Some(count) = tap_plan(line) {
+                planned = Some(count);
+            } else if line.starts_with("Bail out!") {
+                bailed_out = true;
+                push_line(&mut report.output, line);
+            } else if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                // Subtests and their output belong to the next top level test.
+                push_line(&mut pending_output, line);
+            } else if line.starts_with('#') && !line.starts_with("# Subtest") {
+                match report.cases.last_mut() {
+                    Some(case) if case.outcome == TestOutcome::Failed => {
+                        push_line(&mut case.output, line)
+                    }
+                    _ => push_line(&mut report.output, line),
+                }
+            } else {
+                push_line(&mut report.output, line);
+            }
+        }
+        report.output.push_str(&pending_output);
+
+        let all_passed = report
+            .cases
+            .iter()
+            .all(|case| matches!(case.outcome, TestOutcome::Passed | TestOutcome::Skipped));
+        let complete = planned.is_none_or(|count| count == report.cases.len());
+        report.passed = all_passed && complete && !bailed_out;
+        if bailed_out {
+            report.failure = Some(TestResult::Crash);
+        }
+        report
+    }
+}
+
+/// A test line in TAP output.
+struct TapTest<'a> {
+    outcome: TestOutcome,
+    description: &'a str,
+}
+
+/// Parses a test line like `ok 1 - description # SKIP reason` or `not ok 2 description`.
+/// The description is the test number if there is no description.
+fn tap_test_line(line: &str) -> Option<TapTest<'_>> {
+    let (failed, rest) = if let Some(rest) = line.strip_prefix("not ok") {
+        (true, rest)
+    } else if let Some(rest) = line.strip_prefix("ok") {
+        (false, rest)
+    } else {
+        return None;
+    };
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+
+    let (rest, directive) = match rest.split_once(" # ") {
+        Some((rest, directive)) => (rest, Some(directive.trim_start())),
+        None => (rest, None),
+    };
+    let is_directive = |name: &str| {
+        directive.is_some_and(|directive| {
+            directive
+                .get(..name.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(name))
+        })
+    };
+
+    let outcome = if is_directive("SKIP") || is_directive("TODO") {
+        TestOutcome::Skipped
+    } else if failed {
+        TestOutcome::Failed
+    } else {
+        TestOutcome::Passed
+    };
+
+    let rest = rest.trim();
+    let description = match rest.split_once(char::is_whitespace) {
+        Some((number, description)) if number.chars().all(|c| c.is_ascii_digit()) => {
+            description.trim_start_matches(['-', ' '])
+        }
+        _ => rest,
+    };
+    Some(TapTest {
+        outcome,
+        description,
+    })
+}
+
+/// Parses a plan line like `1..3` or `1..0 # SKIP reason` into the number of planned tests.
+fn tap_plan(line: &str) -> Option<usize> {
+    let count = line.strip_prefix("1..")?;
+    let count = count.split_whitespace().next().unwrap_or(count);
+    count.parse().ok()
+}
+
+/// Parses a `duration_ms: 1.5` entry of a YAML diagnostics block.
+fn yaml_duration(line: &str) -> Option<Duration> {
+    let millis: f64 = line
+        .trim()
+        .strip_prefix("duration_ms:")?
+        .trim()
+        .parse()
+        .ok()?;
+    (millis.is_finite() && millis >= 0.0).then(|| Duration::from_secs_f64(millis / 1000.0))
+}
+
+fn push_line(output: &mut String, line: &str) {
+    output.push_str(line);
+    output.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passed() {
+        let report = TestReport::from_tap("TAP version 13\n1..2\nok 1 - adds\nok 2 - subtracts\n");
+
+        assert!(report.passed);
+        assert_eq!(report.cases.len(), 2);
+        assert_eq!(report.cases[0].name, "adds");
+        assert_eq!(report.result(), TestResult::Success);
+    }
+
+    #[test]
+    fn failed() {
+        let report = TestReport::from_tap("1..2\nok 1 - adds\nnot ok 2 - subtracts\n");
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["subtracts"]
+        );
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn missing_tests() {
+        let report = TestReport::from_tap("1..3\nok 1 - adds\n");
+
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn directives() {
+        let report = TestReport::from_tap(
+            "1..3\nok 1 - a # SKIP not on this platform\nnot ok 2 - b # TODO later\nok 3 c # skip\n",
+        );
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Skipped),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(report.result(), TestResult::Success);
+    }
+
+    #[test]
+    fn yaml_diagnostics() {
+        let output = [
+            "TAP version 13",
+            "# Subtest: adds",
+            "ok 1 - adds",
+            "  ---",
+            "  duration_ms: 0.5",
+            "  ...",
+            "# Subtest: fails",
+            "not ok 2 - fails",
+            "  ---",
+            "  duration_ms: 1500",
+            "  error: 'Expected values to be strictly equal'",
+            "  ...",
+            "1..2",
+        ]
+        .join("\n");
+        let report = TestReport::from_tap(&output);
+
+        let fails = report.case("fails").unwrap();
+        assert_eq!(fails.outcome, TestOutcome::Failed);
+        assert_eq!(fails.elapsed, Some(Duration::from_millis(1500)));
+        assert!(fails.output.contains("strictly equal"));
+        assert_eq!(
+            report.case("adds").unwrap().elapsed,
+            Some(Duration::from_micros(500))
+        );
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn bats_diagnostics() {
+        let output = [
+            "1..2",
+            "not ok 1 addition",
+            "# (in test file test.bats, line 4)",
+            "#   `[ \"$result\" -eq 4 ]' failed",
+            "ok 2 subtraction",
+        ]
+        .join("\n");
+        let report = TestReport::from_tap(&output);
+
+        let addition = report.case("addition").unwrap();
+        assert!(addition.output.contains("test.bats, line 4"));
+        assert!(report.output.is_empty());
+    }
+
+    #[test]
+    fn subtests_belong_to_parent() {
+        let output = [
+            "# Subtest: suite",
+            "    ok 1 - inner a",
+            "    not ok 2 - inner b",
+            "not ok 1 - suite",
+            "1..1",
+        ]
+        .join("\n");
+        let report = TestReport::from_tap(&output);
+
+        assert_eq!(report.cases.len(), 1);
+        assert!(report.cases[0].output.contains("inner b"));
+    }
+
+    #[test]
+    fn bail_out() {
+        let report = TestReport::from_tap("1..3\nok 1 - a\nBail out! Database unavailable\n");
+
+        assert!(!report.passed);
+        assert!(report.output.contains("Database unavailable"));
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn skipped_plan() {
+        let report = TestReport::from_tap("1..0 # SKIP no tests for this platform\n");
+
+        assert!(report.passed);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::exam_tester::exam::{TestReport, TestResult};
+use crate::exam_tester::process::{Language, ResourceLimits, Sandbox};
+use crate::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the task configuration file in a task directory.
+pub const TASK_CONFIG_FILE: &str = "task.toml";
+
+/// A task of an exam with its settings,
+/// combined from the task's configuration file and the exam configuration.
+/// Created by [`ExamInfo::task`](crate::exam_tester::exam::ExamInfo::task).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskInfo {
+    /// The name of the task, i.e. the name of the task directory.
+    pub name: String,
+    /// The task directory.
+    pub dir: PathBuf,
+    /// The maximum number of points for the task, if set.
+    pub points: Option<f64>,
+    /// The test timeout for the task.
+    pub timeout: Duration,
+    /// The language of the task, either set explicitly or detected.
+    /// `None` if the language could not be detected.
+    pub language: Option<Language>,
+    /// Patterns of the test files, if set explicitly.
+    pub test_patterns: Option<Vec<String>>,
+    /// Patterns of the tests that decide the grading, if set explicitly
+    /// (see [`TaskInfo::grading_test_patterns`]).
+    pub grading_tests: Vec<String>,
+    /// The files a solution consists of, if set explicitly.
+    pub solution_files: Option<Vec<String>>,
+    /// The resource limits for the test runs of the task.
+    pub limits: ResourceLimits,
+    /// The sandbox the tests of the task are run in, if any.
+    pub sandbox: Option<Sandbox>,
+}
+
+impl TaskInfo {
+    /// Returns the patterns of all test files copied to the submissions,
+    /// i.e. the configured patterns (or else the patterns of the task's language)
+    /// together with the patterns of the grading tests.
+    pub fn test_file_patterns(&self) -> Vec<String> {
+        let mut patterns = match (&self.test_patterns, &self.language) {
+            (Some(patterns), _) => patterns.clone(),
+            (None, Some(language)) => language.test_file_patterns(),
+            (None, None) => Vec::new(),
+        };
+        for pattern in &self.grading_tests {
+            if !patterns.contains(pattern) {
+                patterns.push(pattern.clone());
+            }
+        }
+        patterns
+    }
+
+    /// Returns the patterns of the grading tests, i.e. the tests that are not handed out
+    /// with the assignment. These are the configured grading tests, or else the grading tests
+    /// of the task's language (e.g. `*_grading_test.go` for Go).
+    pub fn grading_test_patterns(&self) -> Vec<String> {
+        match (self.grading_tests.is_empty(), &self.language) {
+            (false, _) => self.grading_tests.clone(),
+            (true, Some(language)) => language.grading_test_patterns(),
+            (true, None) => Vec::new(),
+        }
+    }
+
+    /// Returns the files a solution of the task consists of.
+    /// If not set explicitly, this is the source file of the task's language
+    /// (e.g. `task_1.go` for the Go task `task_1`).
+    /// The first file is the one the test results are appended to.
+    pub fn solution_files(&self) -> Vec<String> {
+        match (&self.solution_files, &self.language) {
+            (Some(files), _) => files.clone(),
+            (None, Some(language)) => vec![language.source_file(&self.name)],
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Checks a student's submission of the task in the given directory
+    /// (containing the submission together with the tests) before running the tests.
+    /// Returns a report if the tests are not run:
+    /// * [`TestResult::NotSubmitted`] if the directory is missing or contains test files only,
+    /// * [`TestResult::MissingSourceFile`] if one of the solution files is missing.
+    pub fn check_submission(&self, dir: &Path) -> Result<Option<TestReport>, Error> {
+        if !dir.is_dir() {
+            return Ok(Some(TestReport::not_tested(
+                TestResult::NotSubmitted,
+                "task directory missing",
+            )));
+        }
+        let mut test_files = Vec::new();
+        for pattern in self.test_file_patterns() {
+            test_files.extend(crate::filesystem::files_matching(dir, &pattern)?);
+        }
+        let files = crate::filesystem::files_with_suffix_recursive(dir, "")?;
+        if files.iter().all(|file| test_files.contains(file)) {
+            return Ok(Some(TestReport::not_tested(
+                TestResult::NotSubmitted,
+                "task directory contains test files only",
+            )));
+        }
+
+        let missing: Vec<String> = self
+            .solution_files()
+            .into_iter()
+            .filter(|file| !dir.join(file).is_file())
+            .collect();
+        if !missing.is_empty() {
+            return Ok(Some(TestReport::not_tested(
+                TestResult::MissingSourceFile,
+                format!("missing source files: {}", missing.join(", ")),
+            )));
+        }
+        Ok(None)
+    }
+
+    /// Marks the given report of a test run as [`TestResult::NoTestsRun`]
+    /// if no tests were reported and the run didn't fail as a whole (e.g. to build).
+    /// Only applies to languages whose runners report the individual tests
+    /// (see [`Language::reports_tests`]).
+    pub fn check_tests_run(&self, report: &mut TestReport) {
+        let reports_tests = self
+            .language
+            .as_ref()
+            .is_some_and(|language| language.reports_tests());
+        if reports_tests && report.cases.is_empty() && report.failure.is_none() {
+            report.failure = Some(TestResult::NoTestsRun);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn go_task() -> TaskInfo {
+        TaskInfo {
+            name: "task_1".to_string(),
+            dir: PathBuf::from("aufgaben/task_1"),
+            points: None,
+            timeout: Duration::from_secs(30),
+            language: Some(Language::Go),
+            test_patterns: None,
+            grading_tests: Vec::new(),
+            solution_files: None,
+            limits: ResourceLimits::default(),
+            sandbox: None,
+        }
+    }
+
+    #[test]
+    fn language_defaults() {
+        let task = go_task();
+
+        assert_eq!(task.test_file_patterns(), vec!["*_test.go"]);
+        assert_eq!(task.solution_files(), vec!["task_1.go"]);
+    }
+
+    #[test]
+    fn configured_files() {
+        let task = TaskInfo {
+            test_patterns: Some(vec!["*_test.go".to_string()]),
+            grading_tests: vec!["*_grading_test.go".to_string(), "*_test.go".to_string()],
+            solution_files: Some(vec!["list.go".to_string(), "sort.go".to_string()]),
+            ..go_task()
+        };
+
+        assert_eq!(
+            task.test_file_patterns(),
+            vec!["*_test.go", "*_grading_test.go"]
+        );
+        assert_eq!(task.solution_files(), vec!["list.go", "sort.go"]);
+    }
+
+    #[test]
+    fn check_submission() {
+        let task = go_task();
+        let dir = tempfile::tempdir().unwrap();
+        let check = |dir: &Path| {
+            task.check_submission(dir)
+                .unwrap()
+                .map(|report| report.result())
+        };
+
+        assert_eq!(
+            check(&dir.path().join("task_1")),
+            Some(TestResult::NotSubmitted)
+        );
+        std::fs::write(dir.path().join("task_1_test.go"), "package task_1\n").unwrap();
+        assert_eq!(check(dir.path()), Some(TestResult::NotSubmitted));
+        std::fs::write(dir.path().join("helper.go"), "package task_1\n").unwrap();
+        assert_eq!(check(dir.path()), Some(TestResult::MissingSourceFile));
+        std::fs::write(dir.path().join("task_1.go"), "package task_1\n").unwrap();
+        assert_eq!(check(dir.path()), None);
+    }
+
+    #[test]
+    fn check_tests_run() {
+        let task = go_task();
+        let mut report = TestReport {
+            passed: true,
+            output: "?   \tgo-exam/task_1\t[no test files]\n".to_string(),
+            ..Default::default()
+        };
+
+        task.check_tests_run(&mut report);
+
+        assert_eq!(report.result(), TestResult::NoTestsRun);
+    }
+}
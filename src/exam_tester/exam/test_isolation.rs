@@ -0,0 +1,220 @@
+use crate::exam_tester::exam::TaskInfo;
+use crate::Error;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Directory next to the task directory the student's tests are moved to by
+/// [`TestIsolation::Quarantine`]. Go ignores directories starting with `_`.
+pub const QUARANTINE_DIR: &str = "_student_tests";
+
+/// Suffix appended to the student's tests by [`TestIsolation::Rename`].
+pub const RENAMED_TEST_SUFFIX: &str = ".student";
+
+/// How the tests in a student's submission are handled in the copy the tests are run in
+/// (see [`StagedTask`](crate::exam_tester::exam::StagedTask)).
+///
+/// Students might change the tests delivered with the assignment
+/// (e.g. while experimenting) or add tests of their own,
+/// which might not compile or fail for reasons unrelated to their solution.
+/// Unless the tests are kept, all test files of the submission are taken out of the run
+/// and the tests deciding the grading are copied from the task directory,
+/// replacing files of the same name.
+/// These are the grading tests of the task (see [`TaskInfo::grading_test_patterns`],
+/// e.g. `*_grading_test.go` for Go), the same tests that are left out of the assignment.
+/// If there are none, e.g. for languages without a convention for grading tests,
+/// all test files of the task decide.
+///
+/// The grading directory itself is never changed.
+/// In configuration files, the policy is set by the `test_isolation` key of the
+/// `[grading]` table, e.g. `test_isolation = "exclude"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestIsolation {
+    /// The tests of the submission are run together with the tests of the task.
+    #[default]
+    Keep,
+    /// The tests of the submission are removed.
+    Exclude,
+    /// The tests of the submission are moved to [`QUARANTINE_DIR`]
+    /// next to the task directory, i.e. out of the package or project.
+    Quarantine,
+    /// The tests of the submission are renamed by appending [`RENAMED_TEST_SUFFIX`],
+    /// so they are ignored by the toolchains.
+    Rename,
+}
+
+impl TestIsolation {
+    /// Applies the policy to the given task directory of a student's submission,
+    /// which is expected to be the copy the tests are run in.
+    pub fn apply(self, task: &TaskInfo, dir: &Path) -> Result<(), Error> {
+        if self == TestIsolation::Keep {
+            return Ok(());
+        }
+        for pattern in task.test_file_patterns() {
+            for file in crate::filesystem::files_matching(dir, &pattern)? {
+                let path = dir.join(&file);
+                match self {
+                    TestIsolation::Keep => {}
+                    TestIsolation::Exclude => {
+                        fs::remove_file(&path).map_err(|e| Error::io("remove", &path, e))?;
+                    }
+                    TestIsolation::Quarantine => {
+                        let quarantine_dir = dir.with_file_name(QUARANTINE_DIR).join(&task.name);
+                        move_file(&path, &quarantine_dir.join(&file))?;
+                    }
+                    TestIsolation::Rename => {
+                        let mut renamed = path.clone().into_os_string();
+                        renamed.push(RENAMED_TEST_SUFFIX);
+                        move_file(&path, Path::new(&renamed))?;
+                    }
+                }
+            }
+        }
+
+        let mut deciding_patterns = task.grading_test_patterns();
+        if deciding_patterns.is_empty() {
+            deciding_patterns = task.test_file_patterns();
+        }
+        let mut deciding_tests = Vec::new();
+        for pattern in &deciding_patterns {
+            deciding_tests.extend(crate::filesystem::files_matching(&task.dir, pattern)?);
+        }
+        crate::filesystem::copy_files(&deciding_tests, &task.dir, dir, &mut |_, _| true)
+    }
+}
+
+impl FromStr for TestIsolation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(TestIsolation::Keep),
+            "exclude" => Ok(TestIsolation::Exclude),
+            "quarantine" => Ok(TestIsolation::Quarantine),
+            "rename" => Ok(TestIsolation::Rename),
+            _ => Err(format!(
+                "unknown test isolation {:?}, expected keep, exclude, quarantine or rename",
+                s
+            )),
+        }
+    }
+}
+
+/// Moves the given file, creating the parent directories of the destination.
+fn move_file(src: &Path, dest: &Path) -> Result<(), Error> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| Error::io("create directory", parent, e))?;
+    }
+    fs::rename(src, dest).map_err(|e| Error::io("move", src, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exam_tester::process::{Language, ResourceLimits};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// Creates a Go task with a delivered and a grading test
+    /// and a submission with a modified delivered test and a test of the student.
+    fn setup(grading_tests: &[&str]) -> (tempfile::TempDir, TaskInfo, PathBuf) {
+        let exam_dir = tempfile::tempdir().unwrap();
+        let task_dir = exam_dir.path().join("aufgaben/task_1");
+        let submission_dir = exam_dir.path().join("bewertung/student_1/task_1");
+        fs::create_dir_all(&task_dir).unwrap();
+        fs::create_dir_all(&submission_dir).unwrap();
+        fs::write(task_dir.join("task_1_test.go"), "original").unwrap();
+        fs::write(task_dir.join("task_1_grading_test.go"), "grading").unwrap();
+        fs::write(submission_dir.join("task_1.go"), "solution").unwrap();
+        fs::write(submission_dir.join("task_1_test.go"), "modified").unwrap();
+        fs::write(submission_dir.join("extra_test.go"), "extra").unwrap();
+        let task = TaskInfo {
+            name: "task_1".to_string(),
+            dir: task_dir,
+            points: None,
+            timeout: Duration::from_secs(30),
+            language: Some(Language::Go),
+            test_patterns: None,
+            grading_tests: grading_tests.iter().map(|p| p.to_string()).collect(),
+            solution_files: None,
+            limits: ResourceLimits::default(),
+            sandbox: None,
+        };
+        (exam_dir, task, submission_dir)
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        crate::filesystem::files_with_suffix_recursive(dir, "").unwrap()
+    }
+
+    #[test]
+    fn exclude_keeps_grading_tests_only() {
+        let (_exam_dir, task, dir) = setup(&["*_grading_test.go"]);
+
+        TestIsolation::Exclude.apply(&task, &dir).unwrap();
+
+        assert_eq!(files(&dir), ["task_1.go", "task_1_grading_test.go"]);
+    }
+
+    #[test]
+    fn exclude_default_grading_tests() {
+        let (_exam_dir, task, dir) = setup(&[]);
+
+        TestIsolation::Exclude.apply(&task, &dir).unwrap();
+
+        assert_eq!(files(&dir), ["task_1.go", "task_1_grading_test.go"]);
+    }
+
+    #[test]
+    fn exclude_restores_task_tests() {
+        let (_exam_dir, mut task, dir) = setup(&[]);
+        task.language = Some(Language::Python);
+        task.test_patterns = Some(vec!["*_test.go".to_string()]);
+
+        TestIsolation::Exclude.apply(&task, &dir).unwrap();
+
+        assert_eq!(
+            files(&dir),
+            ["task_1.go", "task_1_grading_test.go", "task_1_test.go"]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("task_1_test.go")).unwrap(),
+            "original"
+        );
+    }
+
+    #[test]
+    fn quarantine_and_rename() {
+        let (_exam_dir, task, dir) = setup(&["*_grading_test.go"]);
+
+        TestIsolation::Quarantine.apply(&task, &dir).unwrap();
+
+        assert_eq!(files(&dir), ["task_1.go", "task_1_grading_test.go"]);
+        assert_eq!(
+            files(&dir.with_file_name(QUARANTINE_DIR)),
+            ["task_1/extra_test.go", "task_1/task_1_test.go"]
+        );
+
+        let (_exam_dir, task, dir) = setup(&["*_grading_test.go"]);
+
+        TestIsolation::Rename.apply(&task, &dir).unwrap();
+
+        assert_eq!(
+            files(&dir),
+            [
+                "extra_test.go.student",
+                "task_1.go",
+                "task_1_grading_test.go",
+                "task_1_test.go.student"
+            ]
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("rename".parse(), Ok(TestIsolation::Rename));
+        assert!("delete".parse::<TestIsolation>().is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::exam_tester::exam::TestResult;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Structured result of a single test run, i.e. of running all tests
+/// of one task for one student.
+///
+/// In contrast to [`TestResult`], the report keeps track of the individual tests.
+/// The coarse result can be computed from the report via [`TestReport::result`].
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct TestReport {
+    /// The individual tests in the order in which they finished.
+    pub cases: Vec<TestCase>,
+    /// Whether the test run as a whole passed according to the test framework.
+    pub passed: bool,
+    /// Failure of the run as a whole that is not attributed to a single test,
+    /// e.g. a build failure or a timeout.
+    pub failure: Option<TestResult>,
+    /// Output that is not attributed to a single test, e.g. build output.
+    pub output: String,
+}
+
+/// Result of a single test within a test run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestCase {
+    /// The name of the test as reported by the test framework.
+    pub name: String,
+    /// The outcome of the test.
+    pub outcome: TestOutcome,
+    /// The time the test took, if reported by the test framework.
+    pub elapsed: Option<Duration>,
+    /// The output produced while the test was running.
+    pub output: String,
+}
+
+/// Outcome of a single test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TestOutcome {
+    /// The test passed.
+    Passed,
+    /// The test failed, i.e. an assertion did not hold.
+    Failed,
+    /// The test was skipped.
+    Skipped,
+    /// The test crashed (e.g. panic/exception, segfault).
+    Crashed,
+    /// The test ran into a stack overflow.
+    StackOverflow,
+}
+
+impl TestReport {
+    /// Creates a report for a submission that was not tested for the given reason,
+    /// e.g. [`TestResult::NotSubmitted`], with the given explanation.
+    pub fn not_tested<S: Into<String>>(result: TestResult, output: S) -> Self {
+        Self {
+            failure: Some(result),
+            output: output.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Computes the coarse result of the test run.
+    ///
+    /// The most severe of the following is returned:
+    /// the failure of the run as a whole, the outcomes of the individual tests,
+    /// and a test failure if the run did not pass.
+    pub fn result(&self) -> TestResult {
+        let run_result = if self.passed {
+            TestResult::Success
+        } else {
+            TestResult::TestFailure
+        };
+
+        self.cases
+            .iter()
+            .map(|case| case.outcome.result())
+            .chain(self.failure)
+            .fold(run_result, TestResult::most_severe)
+    }
+
+    /// Returns the names of all tests with the given outcome.
+    pub fn tests_with_outcome(&self, outcome: TestOutcome) -> Vec<&str> {
+        self.cases
+            .iter()
+            .filter(|case| case.outcome == outcome)
+            .map(|case| case.name.as_str())
+            .collect()
+    }
+
+    /// Returns the test with the given name, if it was reported.
+    pub fn case(&self, name: &str) -> Option<&TestCase> {
+        self.cases.iter().find(|case| case.name == name)
+    }
+}
+
+impl TestOutcome {
+    /// Returns the coarse result corresponding to the outcome of a single test.
+    pub fn result(&self) -> TestResult {
+        use TestOutcome::*;
+        match self {
+            Passed | Skipped => TestResult::Success,
+            Failed => TestResult::TestFailure,
+            Crashed => TestResult::Crash,
+            StackOverflow => TestResult::StackOverflow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, outcome: TestOutcome) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            outcome,
+            elapsed: None,
+            output: String::new(),
+        }
+    }
+
+    #[test]
+    fn result_passed_without_cases() {
+        let report = TestReport {
+            passed: true,
+            ..Default::default()
+        };
+        assert_eq!(report.result(), TestResult::Success);
+    }
+
+    #[test]
+    fn result_not_passed_without_cases() {
+        let report = TestReport::default();
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn result_most_severe_case() {
+        let report = TestReport {
+            cases: vec![
+                case("a", TestOutcome::Passed),
+                case("b", TestOutcome::Crashed),
+                case("c", TestOutcome::Failed),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(report.result(), TestResult::Crash);
+        assert_eq!(report.tests_with_outcome(TestOutcome::Failed), vec!["c"]);
+    }
+
+    #[test]
+    fn result_run_failure_wins() {
+        let report = TestReport {
+            cases: vec![case("a", TestOutcome::StackOverflow)],
+            failure: Some(TestResult::Timeout),
+            ..Default::default()
+        };
+        assert_eq!(report.result(), TestResult::Timeout);
+    }
+}
@@ -1,7 +1,10 @@
+use crate::exam_tester::exam::MessageLanguage;
+use serde::{Deserialize, Serialize};
+
 /// Represents the result of a test run.
 /// Note that, in contrast to regular testing in software development,
 /// everything is a regular test result, including crashes, build failures, etc.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TestResult {
     /// Successful test run.
     Success,
@@ -12,15 +15,31 @@ pub enum TestResult {
     StackOverflow,
     /// General crash during run (e.g. panic/exception, segfault).
     Crash,
+    /// The memory limit was exceeded during the test run,
+    /// e.g. the test process was killed by the OOM killer or failed to allocate memory.
+    MemoryLimitExceeded,
     /// Test times out.
     Timeout,
     /// Build failed.
     BuildFailure,
+    /// The tests ran, but no test was found or executed,
+    /// e.g. because the test files are missing or don't contain any tests.
+    NoTestsRun,
+    /// The student handed in the task, but a source file of the solution is missing.
+    /// No tests are run in this case.
+    MissingSourceFile,
+    /// The student didn't hand in the task,
+    /// i.e. the task directory is missing or contains the tests only.
+    /// No tests are run in this case.
+    NotSubmitted,
 }
 
 impl TestResult {
     /// Parses the output of a go test and returns the corresponding TestResult.
     /// Note: This parses a string and thus may not be reliable/future proof.
+    /// Prefer running `go test -json` and using [`TestReport::from_go_test_json`].
+    ///
+    /// [`TestReport::from_go_test_json`]: crate::exam_tester::exam::TestReport::from_go_test_json
     pub fn from_go_test_output(test_output: &str) -> Self {
         let build_failure = test_output.contains("[build failed]");
         let timeout = test_output.contains("panic: test timed out after");
@@ -44,6 +63,37 @@ impl TestResult {
         }
     }
 
+    /// Returns the more severe of the two results.
+    /// Severity follows the order in which results are checked when parsing,
+    /// i.e. a build failure is more severe than a timeout, which is more severe than
+    /// an exceeded memory limit, a stack overflow, a crash, a test run without tests,
+    /// a test failure and finally a success.
+    /// Results of submissions that were not tested at all (not submitted, missing source file)
+    /// are the most severe.
+    pub fn most_severe(self, other: Self) -> Self {
+        if other.severity() > self.severity() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn severity(&self) -> u8 {
+        use TestResult::*;
+        match self {
+            Success => 0,
+            TestFailure => 1,
+            NoTestsRun => 2,
+            Crash => 3,
+            StackOverflow => 4,
+            MemoryLimitExceeded => 5,
+            Timeout => 6,
+            BuildFailure => 7,
+            MissingSourceFile => 8,
+            NotSubmitted => 9,
+        }
+    }
+
     /// Returns a string representation of the TestResult in English.
     pub fn to_string_en(&self) -> String {
         use TestResult::*;
@@ -52,8 +102,12 @@ impl TestResult {
             TestFailure => "Test Failure",
             StackOverflow => "Stack Overflow",
             Crash => "Crash",
+            MemoryLimitExceeded => "Memory Limit Exceeded",
             Timeout => "Timeout",
             BuildFailure => "Build Failure",
+            NoTestsRun => "No Tests Run",
+            MissingSourceFile => "Missing Source File",
+            NotSubmitted => "Not Submitted",
         }
         .to_string()
     }
@@ -66,17 +120,30 @@ impl TestResult {
             TestFailure => "Test-Fehlschlag",
             StackOverflow => "Stack Overflow",
             Crash => "Absturz",
+            MemoryLimitExceeded => "Speicherlimit überschritten",
             Timeout => "Timeout",
             BuildFailure => "Build-Fehlschlag",
+            NoTestsRun => "Keine Tests ausgeführt",
+            MissingSourceFile => "Quelldatei fehlt",
+            NotSubmitted => "Nicht abgegeben",
         }
         .to_string()
     }
+
+    /// Returns a string representation of the TestResult in the given language.
+    pub fn to_string_in(&self, language: MessageLanguage) -> String {
+        match language {
+            MessageLanguage::De => self.to_string_de(),
+            MessageLanguage::En => self.to_string_en(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[allow(clippy::useless_vec, clippy::needless_return)]
     mod build_results {
         pub fn build_failure() -> String {
             vec!["...", "FAIL    path/to/module.go [build failed]", "..."].join("\n")
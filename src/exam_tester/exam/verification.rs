@@ -0,0 +1,225 @@
+use crate::exam_tester::exam::{TestOutcome, TestReport, TestResult};
+use crate::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Number of times the tests of each task are run against the reference solution.
+pub const VERIFICATION_RUNS: usize = 3;
+
+/// Fraction of the timeout above which the duration of a test run
+/// against the reference solution is reported. Submissions are often slower
+/// than the reference solution, so they might time out although they are correct.
+pub const NEAR_TIMEOUT_FRACTION: f64 = 0.5;
+
+/// The result of verifying the tests of the tasks against the reference solutions,
+/// see [`ExamTester::verify`](crate::exam_tester::exam::ExamTester::verify).
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    /// The verified tasks in the order they were verified.
+    pub tasks: Vec<String>,
+    /// The issues found, in the order of the tasks.
+    pub issues: Vec<VerificationIssue>,
+}
+
+/// An issue with the tests of a task found while verifying them against the reference solution.
+#[derive(Debug)]
+pub struct VerificationIssue {
+    /// The name of the task.
+    pub task: String,
+    pub kind: IssueKind,
+}
+
+/// The kinds of issues found by the verification.
+#[derive(Debug)]
+pub enum IssueKind {
+    /// The tests don't pass with the reference solution in any run.
+    /// Contains the result and the names of the tests that didn't pass, if reported.
+    Failed {
+        result: TestResult,
+        tests: Vec<String>,
+    },
+    /// The tests pass in some runs only, or fail differently in different runs.
+    /// Contains the names of the tests with differing outcomes, if reported.
+    Flaky { tests: Vec<String> },
+    /// The tests pass, but the slowest run took more than
+    /// [`NEAR_TIMEOUT_FRACTION`] of the timeout.
+    /// This is only a warning, as the duration includes staging and building the tests,
+    /// which might be slow e.g. with a cold build cache.
+    NearTimeout {
+        elapsed: Duration,
+        timeout: Duration,
+    },
+    /// The tests could not be run, e.g. because the language of the task is unknown.
+    NotRun(Error),
+}
+
+impl VerificationReport {
+    /// Returns whether no issues other than warnings were found, i.e. whether grading can start.
+    pub fn passed(&self) -> bool {
+        self.issues.iter().all(|issue| issue.kind.is_warning())
+    }
+
+    /// Returns the tasks with issues, including warnings, without duplicates.
+    pub fn tasks_with_issues(&self) -> Vec<&str> {
+        let mut tasks: Vec<&str> = self.issues.iter().map(|i| i.task.as_str()).collect();
+        tasks.dedup();
+        tasks
+    }
+
+    /// Returns the tasks with issues other than warnings, without duplicates.
+    pub fn failed_tasks(&self) -> Vec<&str> {
+        let mut tasks: Vec<&str> = self
+            .issues
+            .iter()
+            .filter(|issue| !issue.kind.is_warning())
+            .map(|issue| issue.task.as_str())
+            .collect();
+        tasks.dedup();
+        tasks
+    }
+
+    /// Returns the issues as text, one issue per line.
+    pub fn to_text(&self) -> String {
+        self.issues
+            .iter()
+            .map(|issue| format!("{}\n", issue))
+            .collect()
+    }
+}
+
+impl IssueKind {
+    /// Returns whether the issue is only a warning, which doesn't fail the verification.
+    pub fn is_warning(&self) -> bool {
+        matches!(self, IssueKind::NearTimeout { .. })
+    }
+
+    /// Evaluates the reports and durations of the test runs of a task
+    /// against the reference solution, run with the given timeout.
+    pub fn from_runs(runs: &[(TestReport, Duration)], timeout: Duration) -> Option<IssueKind> {
+        let results: Vec<TestResult> = runs.iter().map(|(report, _)| report.result()).collect();
+        if results.iter().all(|&result| result == TestResult::Success) {
+            let elapsed = runs.iter().map(|&(_, elapsed)| elapsed).max()?;
+            return (elapsed.as_secs_f64() > timeout.as_secs_f64() * NEAR_TIMEOUT_FRACTION)
+                .then_some(IssueKind::NearTimeout { elapsed, timeout });
+        }
+
+        let failing: Vec<Vec<&str>> = runs
+            .iter()
+            .map(|(report, _)| failing_tests(report))
+            .collect();
+        let consistent = results.windows(2).all(|pair| pair[0] == pair[1])
+            && failing.windows(2).all(|pair| pair[0] == pair[1]);
+        if consistent {
+            return Some(IssueKind::Failed {
+                result: results[0],
+                tests: failing[0].iter().map(|test| test.to_string()).collect(),
+            });
+        }
+        let mut tests: Vec<String> = failing
+            .iter()
+            .flatten()
+            .filter(|test| !failing.iter().all(|tests| tests.contains(test)))
+            .map(|test| test.to_string())
+            .collect();
+        tests.sort();
+        tests.dedup();
+        Some(IssueKind::Flaky { tests })
+    }
+}
+
+/// Returns the names of the tests of the report that didn't pass.
+fn failing_tests(report: &TestReport) -> Vec<&str> {
+    report
+        .cases
+        .iter()
+        .filter(|case| !matches!(case.outcome, TestOutcome::Passed | TestOutcome::Skipped))
+        .map(|case| case.name.as_str())
+        .collect()
+}
+
+impl fmt::Display for VerificationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.task)?;
+        match &self.kind {
+            IssueKind::Failed { result, tests } => {
+                write!(
+                    f,
+                    "tests don't pass with the reference solution ({})",
+                    result.to_string_en()
+                )?;
+                if !tests.is_empty() {
+                    write!(f, ": {}", tests.join(", "))?;
+                }
+                Ok(())
+            }
+            IssueKind::Flaky { tests } => {
+                write!(f, "flaky tests, the results differ between runs")?;
+                if !tests.is_empty() {
+                    write!(f, ": {}", tests.join(", "))?;
+                }
+                Ok(())
+            }
+            IssueKind::NearTimeout { elapsed, timeout } => write!(
+                f,
+                "warning: tests took {:.1}s of the {:.1}s timeout with the reference solution",
+                elapsed.as_secs_f64(),
+                timeout.as_secs_f64()
+            ),
+            IssueKind::NotRun(e) => write!(f, "tests could not be run: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exam_tester::exam::TestCase;
+
+    fn report(outcomes: &[(&str, TestOutcome)]) -> TestReport {
+        TestReport {
+            passed: outcomes
+                .iter()
+                .all(|(_, outcome)| *outcome == TestOutcome::Passed),
+            cases: outcomes
+                .iter()
+                .map(|&(name, outcome)| TestCase {
+                    name: name.to_string(),
+                    outcome,
+                    elapsed: None,
+                    output: String::new(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn from_runs() {
+        use TestOutcome::*;
+        let timeout = Duration::from_secs(10);
+        let second = Duration::from_secs(1);
+        let passing = report(&[("TestA", Passed), ("TestB", Passed)]);
+        let failing = report(&[("TestA", Passed), ("TestB", Failed)]);
+
+        let runs = [(passing.clone(), second), (passing.clone(), second)];
+        assert!(IssueKind::from_runs(&runs, timeout).is_none());
+
+        let runs = [(passing.clone(), second), (passing.clone(), 6 * second)];
+        assert!(matches!(
+            IssueKind::from_runs(&runs, timeout),
+            Some(IssueKind::NearTimeout { elapsed, .. }) if elapsed == 6 * second
+        ));
+
+        let runs = [(failing.clone(), second), (failing.clone(), second)];
+        assert!(matches!(
+            IssueKind::from_runs(&runs, timeout),
+            Some(IssueKind::Failed { result: TestResult::TestFailure, tests }) if tests == ["TestB"]
+        ));
+
+        let runs = [(passing, second), (failing, second)];
+        assert!(matches!(
+            IssueKind::from_runs(&runs, timeout),
+            Some(IssueKind::Flaky { tests }) if tests == ["TestB"]
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::exam_tester::exam::{TestReport, TestResult};
+use crate::exam_tester::process::{
+    run_sandboxed, Bind, ResourceLimits, RunOutput, Sandbox, TestRunner,
+};
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Test runner for Rust packages using `cargo test`.
+///
+/// The directory is expected to be a cargo package.
+/// Grading tests are typically added as integration tests in the `tests` directory.
+/// Cargo is run with `--offline`, so all dependencies must already be available locally.
+pub struct CargoRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+}
+
+impl CargoRunner {
+    pub fn new<P: Into<PathBuf>, D: Into<Duration>>(dir: P, timeout: D) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
+        }
+    }
+
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the sandbox (see [`TestRunner`]), none by default.
+    /// Cargo and rustup must find their installations without `HOME`
+    /// (e.g. via `CARGO_HOME` and `RUSTUP_HOME`), which must be mounted read-only.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    fn cargo_test(&self) -> Command {
+        let mut command = Command::new("cargo");
+        command.current_dir(&self.dir).arg("test").arg("--offline");
+        command
+    }
+}
+
+impl TestRunner for CargoRunner {
+    fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// Builds the tests using `cargo test --no-run`.
+    /// As there is no timeout option for cargo, the build is subject to the runner's timeout.
+    fn build(&self) -> io::Result<Option<TestReport>> {
+        let output = run_sandboxed(
+            self.cargo_test().arg("--no-run"),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[Bind::Writable(&self.dir)],
+        )?;
+        Ok(TestReport::from_build_output(&output))
+    }
+
+    /// Runs `cargo test`, killing it if it exceeds the runner's timeout.
+    fn run(&self) -> io::Result<RunOutput> {
+        run_sandboxed(
+            &mut self.cargo_test(),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[],
+        )
+    }
+
+    /// Parses the libtest output from stdout and cargo's messages from stderr.
+    fn parse(&self, output: &RunOutput) -> TestReport {
+        let test_output = format!("{}\n{}", output.stdout_lossy(), output.stderr_lossy());
+        let mut report = TestReport::from_libtest_output(&test_output);
+        if output.timed_out {
+            report.failure = Some(TestResult::Timeout);
+        }
+        report.passed &= output.status.success();
+        report
+    }
+}
@@ -0,0 +1,325 @@
+use crate::exam_tester::exam::{TestReport, TestResult};
+use crate::exam_tester::process::{
+    run_sandboxed, Bind, ResourceLimits, RunOutput, Sandbox, TestRunner,
+};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Placeholder in the test command that is replaced by the path of the report file.
+pub const REPORT_PLACEHOLDER: &str = "{report}";
+
+/// Format of the results reported by a test command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportFormat {
+    /// A JUnit XML report.
+    #[serde(rename = "junit")]
+    JUnitXml,
+    /// Test Anything Protocol output.
+    Tap,
+    /// No report, the exit code decides whether the tests passed.
+    #[default]
+    ExitCode,
+}
+
+/// Configuration for testing with arbitrary commands.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandConfig {
+    /// Shell command building the submission, if a build is needed.
+    #[serde(default)]
+    pub build_command: Option<String>,
+    /// Shell command running the tests.
+    ///
+    /// If the command contains `{report}`, it is replaced by the (quoted) path of a temporary file
+    /// and the report is read from that file. Otherwise, the report is read from stdout.
+    pub test_command: String,
+    /// The format of the report.
+    #[serde(default)]
+    pub report_format: ReportFormat,
+    /// Patterns of test files relative to the task directory, e.g. `*.bats`.
+    #[serde(default)]
+    pub test_patterns: Vec<String>,
+    /// Name of the source file results are appended to, `{task}` is replaced by the task name,
+    /// e.g. `{task}.sh`. There is no default, as the extension depends on the language.
+    pub source_file: String,
+    /// Prefix for line comments in the source file, e.g. `#`.
+    #[serde(default = "default_comment_prefix")]
+    pub comment_prefix: String,
+}
+
+impl CommandConfig {
+    /// Creates a configuration running the given test command,
+    /// reading a report in the given format and appending results to the given source file.
+    pub fn new<S: Into<String>, F: Into<String>>(
+        test_command: S,
+        report_format: ReportFormat,
+        source_file: F,
+    ) -> Self {
+        Self {
+            build_command: None,
+            test_command: test_command.into(),
+            report_format,
+            test_patterns: Vec::new(),
+            source_file: source_file.into(),
+            comment_prefix: default_comment_prefix(),
+        }
+    }
+}
+
+fn default_comment_prefix() -> String {
+    "#".to_string()
+}
+
+/// Test runner using configurable shell commands for building and testing.
+///
+/// This allows testing languages without a dedicated runner,
+/// as long as the test framework reports results as JUnit XML, TAP or via the exit code.
+/// Commands are run with `sh -c` in the task directory.
+pub struct CommandRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+    config: CommandConfig,
+}
+
+impl CommandRunner {
+    pub fn new<P: Into<PathBuf>, D: Into<Duration>>(
+        dir: P,
+        timeout: D,
+        config: CommandConfig,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
+            config,
+        }
+    }
+
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the sandbox (see [`TestRunner`]), none by default.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    fn shell(&self, command: &str) -> Command {
+        let mut shell = Command::new("sh");
+        shell.current_dir(&self.dir).arg("-c").arg(command);
+        shell
+    }
+}
+
+impl TestRunner for CommandRunner {
+    fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// Runs the build command, if there is one.
+    fn build(&self) -> io::Result<Option<TestReport>> {
+        let Some(build_command) = &self.config.build_command else {
+            return Ok(None);
+        };
+        let output = run_sandboxed(
+            &mut self.shell(build_command),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[Bind::Writable(&self.dir)],
+        )?;
+
+        Ok(TestReport::from_build_output(&output))
+    }
+
+    /// Runs the test command and collects the report.
+    fn run(&self) -> io::Result<RunOutput> {
+        let report_dir = tempfile::tempdir()?;
+        let report_file = report_dir.path().join("report");
+        let uses_report_file = self.config.test_command.contains(REPORT_PLACEHOLDER);
+        let test_command = self.config.test_command.replace(
+            REPORT_PLACEHOLDER,
+            &shell_quote(&report_file.to_string_lossy()),
+        );
+
+        let mut output = run_sandboxed(
+            &mut self.shell(&test_command),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[Bind::Writable(report_dir.path())],
+        )?;
+        output.report = if uses_report_file {
+            fs::read_to_string(&report_file).ok()
+        } else {
+            Some(output.stdout_lossy())
+        };
+        Ok(output)
+    }
+
+    /// Parses the report according to the configured format.
+    fn parse(&self, output: &RunOutput) -> TestReport {
+        let report_text = output.report.as_deref();
+        let mut report = match self.config.report_format {
+            ReportFormat::JUnitXml => report_text
+                .and_then(|xml| TestReport::from_junit_xml(xml).ok())
+                .unwrap_or_else(|| TestReport {
+                    failure: Some(TestResult::Crash),
+                    ..Default::default()
+                }),
+            ReportFormat::Tap => TestReport::from_tap(report_text.unwrap_or_default()),
+            ReportFormat::ExitCode => TestReport {
+                passed: true,
+                ..Default::default()
+            },
+        };
+
+        if self.config.report_format != ReportFormat::Tap || output.report.is_none() {
+            report.output.push_str(&output.stdout_lossy());
+        }
+        report.output.push_str(&output.stderr_lossy());
+        if output.timed_out {
+            report.failure = Some(TestResult::Timeout);
+        } else if output.signal().is_some() {
+            report.failure = Some(TestResult::Crash);
+        }
+        report.passed &= output.status.success();
+        report
+    }
+}
+
+/// Quotes the given string for use as a single word in a shell command.
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::exam_tester::exam::TestOutcome;
+
+    fn run(config: CommandConfig) -> TestReport {
+        let temp_dir = tempfile::tempdir().unwrap();
+        CommandRunner::new(temp_dir.path(), Duration::from_secs(10), config)
+            .run_tests()
+            .unwrap()
+    }
+
+    #[test]
+    fn exit_code() {
+        let report = run(CommandConfig::new(
+            "true",
+            ReportFormat::ExitCode,
+            "{task}.sh",
+        ));
+        assert_eq!(report.result(), TestResult::Success);
+
+        let report = run(CommandConfig::new(
+            "exit 1",
+            ReportFormat::ExitCode,
+            "{task}.sh",
+        ));
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn quoted_words() {
+        let word = "report dir/it's $HOME";
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", shell_quote(word)))
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), word);
+    }
+
+    #[test]
+    fn build_failure() {
+        let mut config = CommandConfig::new("true", ReportFormat::ExitCode, "{task}.sh");
+        config.build_command = Some("echo 'syntax error' >&2; exit 1".to_string());
+        let report = run(config);
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+        assert_eq!(report.output, "syntax error\n");
+    }
+
+    /// The build step runs in the sandbox, with the task directory writable.
+    #[test]
+    fn sandboxed_build() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bwrap = temp_dir.path().join("bwrap");
+        let log = temp_dir.path().join("bwrap.log");
+        std::fs::write(
+            &bwrap,
+            format!("#!/bin/sh\necho \"$@\" > {}\nexit 1\n", log.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&bwrap, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+        let task_dir = temp_dir.path().join("task_1");
+        std::fs::create_dir(&task_dir).unwrap();
+        let mut config = CommandConfig::new("true", ReportFormat::ExitCode, "{task}.sh");
+        config.build_command = Some("make".to_string());
+        let sandbox = Sandbox {
+            bwrap,
+            ..Default::default()
+        };
+
+        let report = CommandRunner::new(&task_dir, Duration::from_secs(10), config)
+            .with_sandbox(Some(sandbox))
+            .run_tests()
+            .unwrap();
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+        let args = std::fs::read_to_string(&log).unwrap();
+        let bind = format!("--bind {0} {0} -- sh -c make", task_dir.display());
+        assert!(args.contains(&bind), "{}", args);
+    }
+
+    #[test]
+    fn tap_from_stdout() {
+        let report = run(CommandConfig::new(
+            "printf '1..2\\nok 1 - a\\nnot ok 2 - b\\n'; exit 1",
+            ReportFormat::Tap,
+            "{task}.sh",
+        ));
+
+        assert_eq!(report.tests_with_outcome(TestOutcome::Failed), vec!["b"]);
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+
+    #[test]
+    fn junit_from_report_file() {
+        let report = run(CommandConfig::new(
+            r#"echo '<testsuite><testcase name="a"/></testsuite>' > {report}"#,
+            ReportFormat::JUnitXml,
+            "{task}.sh",
+        ));
+
+        assert_eq!(report.cases.len(), 1);
+        assert_eq!(report.result(), TestResult::Success);
+    }
+
+    #[test]
+    fn killed_by_signal() {
+        let report = run(CommandConfig::new(
+            "kill -SEGV $$",
+            ReportFormat::ExitCode,
+            "{task}.sh",
+        ));
+
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+}
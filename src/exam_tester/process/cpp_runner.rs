@@ -0,0 +1,255 @@
+use crate::exam_tester::exam::{JUnitCase, TestOutcome, TestReport, TestResult};
+use crate::exam_tester::process::{
+    run_sandboxed, Bind, ResourceLimits, RunOutput, Sandbox, TestRunner,
+};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Configuration for building and running C/C++ tests.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CppConfig {
+    /// Shell command building the test executable, run with `sh -c` in the task directory.
+    pub build_command: String,
+    /// Path of the Catch2 test executable created by the build, relative to the task directory.
+    pub test_executable: PathBuf,
+}
+
+impl CppConfig {
+    /// Configuration for a CMake project that builds a test executable named `tests`.
+    pub fn cmake() -> Self {
+        Self {
+            build_command: "cmake -S . -B build && cmake --build build".to_string(),
+            test_executable: PathBuf::from("build/tests"),
+        }
+    }
+
+    /// Configuration compiling all `.cpp` files in the task directory with `g++`
+    /// and linking against Catch2 (v3, including its `main`).
+    pub fn gpp() -> Self {
+        Self {
+            build_command: "g++ -std=c++17 -g -o tests *.cpp -lCatch2Main -lCatch2".to_string(),
+            test_executable: PathBuf::from("tests"),
+        }
+    }
+}
+
+impl Default for CppConfig {
+    fn default() -> Self {
+        Self::cmake()
+    }
+}
+
+/// Test runner for C/C++ using a configurable build command and Catch2.
+///
+/// The test executable is run with Catch2's JUnit reporter.
+/// Crashes are recognized by the signal that terminated the test executable.
+/// Stack overflows cannot be told apart from other segmentation faults by the signal alone,
+/// they are recognized if the tests are built with AddressSanitizer (`-fsanitize=address`).
+pub struct CppRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+    config: CppConfig,
+}
+
+impl CppRunner {
+    pub fn new<P: Into<PathBuf>, D: Into<Duration>>(dir: P, timeout: D) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
+            config: CppConfig::default(),
+        }
+    }
+
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the sandbox (see [`TestRunner`]), none by default.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Sets the build configuration, CMake by default.
+    pub fn with_config(mut self, config: CppConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl TestRunner for CppRunner {
+    fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// Runs the build command.
+    /// The compiler diagnostics are kept as the output of a failed build.
+    fn build(&self) -> io::Result<Option<TestReport>> {
+        let output = run_sandboxed(
+            Command::new("sh")
+                .current_dir(&self.dir)
+                .arg("-c")
+                .arg(&self.config.build_command),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[Bind::Writable(&self.dir)],
+        )?;
+
+        Ok(TestReport::from_build_output(&output))
+    }
+
+    /// Runs the test executable with the JUnit reporter writing to a temporary file.
+    fn run(&self) -> io::Result<RunOutput> {
+        let report_dir = tempfile::tempdir()?;
+        let report_file = report_dir.path().join("report.xml");
+
+        let mut output = run_sandboxed(
+            Command::new(self.dir.join(&self.config.test_executable))
+                .current_dir(&self.dir)
+                .arg("--reporter")
+                .arg("junit")
+                .arg("--out")
+                .arg(&report_file),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[Bind::Writable(report_dir.path())],
+        )?;
+        output.report = fs::read_to_string(&report_file).ok();
+        Ok(output)
+    }
+
+    /// Parses the JUnit report and classifies abnormal terminations by their signal.
+    fn parse(&self, output: &RunOutput) -> TestReport {
+        let cases = output.report.as_deref().map(JUnitCase::parse_all);
+        let mut report = match cases {
+            Some(Ok(cases)) => TestReport::from_junit_cases(&cases, catch2_outcome),
+            _ => TestReport::default(),
+        };
+        report.output.push_str(&output.stdout_lossy());
+        report.output.push_str(&output.stderr_lossy());
+
+        report.failure = if output.timed_out {
+            Some(TestResult::Timeout)
+        } else if is_stack_overflow(&report.output) {
+            Some(TestResult::StackOverflow)
+        } else if output.signal().is_some() {
+            Some(TestResult::Crash)
+        } else {
+            None
+        };
+        report.passed &= output.status.success();
+        report
+    }
+}
+
+/// Classifies a Catch2 test case.
+/// Catch2 reports failed assertions as failures and unexpected exceptions as errors.
+/// Fatal signals caught by Catch2 are reported as failures mentioning the signal.
+fn catch2_outcome(case: &JUnitCase) -> TestOutcome {
+    match case.problem() {
+        Some(problem) if is_stack_overflow(&problem.text) => TestOutcome::StackOverflow,
+        Some(problem)
+            if ["SIGSEGV", "SIGABRT", "SIGFPE", "SIGBUS", "SIGILL"]
+                .iter()
+                .any(|signal| problem.mentions(signal)) =>
+        {
+            TestOutcome::Crashed
+        }
+        _ => case.default_outcome(),
+    }
+}
+
+fn is_stack_overflow(output: &str) -> bool {
+    output.contains("AddressSanitizer: stack-overflow")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    const SIGSEGV: i32 = 11;
+
+    fn parse(status: ExitStatus, stderr: &str, report: Option<&str>) -> TestReport {
+        let output = RunOutput {
+            status,
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+            timed_out: false,
+            report: report.map(str::to_string),
+        };
+        CppRunner::new(".", Duration::from_secs(1)).parse(&output)
+    }
+
+    #[test]
+    fn failure_and_exception() {
+        let report = parse(
+            ExitStatus::from_raw(1 << 8),
+            "",
+            Some(
+                r#"<testsuites><testsuite name="tests">
+                    <testcase classname="tests.global" name="add" time="0.001">
+                        <failure message="add(1, 1) == 2" type="REQUIRE">FAILED: with expansion: 0 == 2</failure>
+                    </testcase>
+                    <testcase classname="tests.global" name="parse" time="0.001">
+                        <error message="boom" type="TEST_CASE">due to unexpected exception</error>
+                    </testcase>
+                    <testcase classname="tests.global" name="other" time="0.001"/>
+                </testsuite></testsuites>"#,
+            ),
+        );
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["tests.global.add"]
+        );
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Crashed),
+            vec!["tests.global.parse"]
+        );
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn segfault_without_report() {
+        let report = parse(ExitStatus::from_raw(SIGSEGV), "", None);
+
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn stack_overflow_with_sanitizer() {
+        let report = parse(
+            ExitStatus::from_raw(1 << 8),
+            "==1==ERROR: AddressSanitizer: stack-overflow on address 0x7ffd",
+            None,
+        );
+
+        assert_eq!(report.result(), TestResult::StackOverflow);
+    }
+
+    #[test]
+    fn success() {
+        let report = parse(
+            ExitStatus::from_raw(0),
+            "",
+            Some(r#"<testsuites><testsuite><testcase name="add"/></testsuite></testsuites>"#),
+        );
+
+        assert_eq!(report.result(), TestResult::Success);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::exam_tester::process::{Bind, ResourceLimits, Sandbox};
+use std::io::{self, Read};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Output of a process run by a test runner.
+#[derive(Debug, Clone)]
+pub struct RunOutput {
+    /// The exit status of the process.
+    pub status: ExitStatus,
+    /// Everything the process wrote to stdout.
+    pub stdout: Vec<u8>,
+    /// Everything the process wrote to stderr.
+    pub stderr: Vec<u8>,
+    /// Whether the process was killed because it exceeded its timeout.
+    pub timed_out: bool,
+    /// Contents of the report file written by the test framework, if any.
+    pub report: Option<String>,
+}
+
+impl RunOutput {
+    /// Returns stdout as a string, replacing invalid UTF-8 sequences.
+    pub fn stdout_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stdout).into_owned()
+    }
+
+    /// Returns stderr as a string, replacing invalid UTF-8 sequences.
+    pub fn stderr_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).into_owned()
+    }
+
+    /// Returns the signal that terminated the process, if any.
+    /// Always `None` on platforms without signals.
+    pub fn signal(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            self.status.signal()
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+}
+
+impl From<Output> for RunOutput {
+    fn from(output: Output) -> Self {
+        Self {
+            status: output.status,
+            stdout: output.stdout,
+            stderr: output.stderr,
+            timed_out: false,
+            report: None,
+        }
+    }
+}
+
+/// Runs the given command, capturing its output.
+/// If the command doesn't finish within the given timeout, it is killed
+/// and the output captured so far is returned with `timed_out` set.
+///
+/// On Unix, the command runs in a process group of its own.
+/// The whole group is killed (with `SIGKILL`, which cannot be handled or ignored)
+/// when the timeout is exceeded, and also when the command finishes,
+/// so processes started by the command (e.g. the test binary started by `go test`
+/// or processes started by student code) cannot outlive the run
+/// or keep the output pipes open.
+pub fn run_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<RunOutput> {
+    run_with_limits(command, timeout, &ResourceLimits::default())
+}
+
+/// Runs the given command like [`run_with_timeout`], with the given resource limits.
+/// Output exceeding the output limit is discarded, which is noted at the end of the output.
+pub fn run_with_limits(
+    command: &mut Command,
+    timeout: Duration,
+    limits: &ResourceLimits,
+) -> io::Result<RunOutput> {
+    limits.apply(command);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let output_limit = limits.output_bytes();
+    let stdout = read_in_background(child.stdout.take(), output_limit);
+    let stderr = read_in_background(child.stderr.take(), output_limit);
+
+    let deadline = Instant::now() + timeout;
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            timed_out = true;
+            kill_process_group(&child);
+            // The child might have exited in the meantime, so its kill may fail.
+            let _ = child.kill();
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    // Processes left behind by the command would keep the pipes open.
+    kill_process_group(&child);
+
+    Ok(RunOutput {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        timed_out,
+        report: None,
+    })
+}
+
+/// Runs the given command like [`run_with_limits`], in the given sandbox if there is one.
+/// The given binds are only used in the sandbox.
+///
+/// Bubblewrap reports the termination of the command by a signal as exit code 128 + signal,
+/// like a shell. This cannot be told apart from a command exiting with such a code
+/// (e.g. `exit(137)` in student code), neither by the exit status nor by the status
+/// reported with `--json-status-fd`. So the exit status is passed on as is
+/// and runners recognize crashes in the sandbox by the reports and the output of the tests,
+/// e.g. Catch2 reports fatal signals and `go test` reports `signal: killed`.
+pub fn run_sandboxed(
+    command: &mut Command,
+    timeout: Duration,
+    limits: &ResourceLimits,
+    sandbox: Option<&Sandbox>,
+    binds: &[Bind],
+) -> io::Result<RunOutput> {
+    let Some(sandbox) = sandbox else {
+        return run_with_limits(command, timeout, limits);
+    };
+    run_with_limits(&mut sandbox.command(command, binds), timeout, limits)
+}
+
+/// Kills all processes in the process group of the given child.
+/// The group still exists after the child was reaped as long as it has other members,
+/// so its id cannot be reused by another process in the meantime.
+#[cfg(unix)]
+fn kill_process_group(child: &Child) {
+    if let Ok(pgid) = libc::pid_t::try_from(child.id()) {
+        // Fails if there are no processes left in the group, which is fine.
+        unsafe { libc::kill(-pgid, libc::SIGKILL) };
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_child: &Child) {}
+
+/// Reads the given pipe to its end in a background thread.
+/// At most `limit` bytes are kept, the rest is read and discarded
+/// so the process writing to the pipe isn't blocked.
+fn read_in_background<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    limit: Option<usize>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let Some(mut pipe) = pipe else {
+            return buffer;
+        };
+        let Some(limit) = limit else {
+            let _ = pipe.read_to_end(&mut buffer);
+            return buffer;
+        };
+
+        let _ = pipe
+            .by_ref()
+            .take(u64::try_from(limit).unwrap_or(u64::MAX))
+            .read_to_end(&mut buffer);
+        let discarded = io::copy(&mut pipe, &mut io::sink()).unwrap_or(0);
+        if discarded > 0 {
+            buffer.extend_from_slice(
+                format!("\n[{} bytes of output discarded]\n", discarded).as_bytes(),
+            );
+        }
+        buffer
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finishes_in_time() {
+        let output = run_with_timeout(
+            Command::new("sh").arg("-c").arg("echo out; echo err >&2"),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+        assert!(output.status.success());
+        assert!(!output.timed_out);
+        assert_eq!(output.stdout_lossy(), "out\n");
+        assert_eq!(output.stderr_lossy(), "err\n");
+    }
+
+    #[test]
+    fn times_out() {
+        let output = run_with_timeout(
+            Command::new("sh")
+                .arg("-c")
+                .arg("echo started; exec sleep 10"),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+
+        assert!(output.timed_out);
+        assert!(!output.status.success());
+        assert_eq!(output.stdout_lossy(), "started\n");
+    }
+
+    #[test]
+    fn sub_second_timeout() {
+        let start = Instant::now();
+        let output = run_with_timeout(
+            Command::new("sh").arg("-c").arg("exec sleep 10"),
+            Duration::from_millis(50),
+        )
+        .unwrap();
+
+        assert!(output.timed_out);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Children ignoring SIGTERM and holding the output pipes open are killed as well.
+    #[test]
+    fn kills_process_group() {
+        let start = Instant::now();
+        let output = run_with_timeout(
+            Command::new("sh")
+                .arg("-c")
+                .arg("trap '' TERM; sleep 10 & sleep 10 & wait"),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+
+        assert!(output.timed_out);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Background processes left behind by a finished command are killed,
+    /// so reading their output doesn't block.
+    #[test]
+    fn kills_leftover_processes() {
+        let start = Instant::now();
+        let output = run_with_timeout(
+            Command::new("sh").arg("-c").arg("sleep 10 & echo done"),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        assert!(!output.timed_out);
+        assert_eq!(output.stdout_lossy(), "done\n");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Exit codes of sandboxed commands are not mistaken for signals.
+    #[test]
+    fn sandboxed_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let bwrap = dir.path().join("bwrap");
+        std::fs::write(&bwrap, "#!/bin/sh\nexit 137\n").unwrap();
+        std::fs::set_permissions(&bwrap, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+        let sandbox = Sandbox {
+            bwrap,
+            ..Default::default()
+        };
+
+        let output = run_sandboxed(
+            Command::new("true").current_dir(dir.path()),
+            Duration::from_secs(10),
+            &ResourceLimits::default(),
+            Some(&sandbox),
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(output.status.code(), Some(137));
+        assert_eq!(output.signal(), None);
+    }
+
+    #[test]
+    fn output_limit() {
+        let limits = ResourceLimits {
+            output_kb: Some(1),
+            ..Default::default()
+        };
+        let output = run_with_limits(
+            Command::new("sh")
+                .arg("-c")
+                .arg("head -c 5000 /dev/zero | tr '\\0' x; echo err >&2"),
+            Duration::from_secs(10),
+            &limits,
+        )
+        .unwrap();
+
+        assert!(output.status.success());
+        assert!(output.stdout_lossy().starts_with(&format!(
+            "{}\n[3976 bytes of output discarded]",
+            "x".repeat(1024)
+        )));
+        assert_eq!(output.stderr_lossy(), "err\n");
+    }
+}
@@ -1,11 +1,31 @@
-use crate::exam_tester::exam::TestResult;
+use crate::exam_tester::exam::{TestReport, TestResult};
+use crate::exam_tester::process::test_runner::{add_exceeded_limit, exceeded_limit_in_output};
+use crate::exam_tester::process::{
+    run_sandboxed, run_with_limits, Bind, Language, ResourceLimits, RunOutput, Sandbox, TestRunner,
+};
+use std::collections::BTreeMap;
+use std::io;
 use std::path::PathBuf;
 use std::process::{Command, Output};
 use std::time::Duration;
 
+/// Time added to the timeout of `go test` for killing the whole `go test` process,
+/// see [`GoRunner`].
+pub const GO_TIMEOUT_GRACE: Duration = Duration::from_secs(5);
+
+/// Test runner for Go packages using `go test`.
+/// The tests are built by `go test` itself, so there is no separate build step.
+///
+/// The timeout is passed to `go test`, which bounds the time of the tests
+/// and reports the running tests when it is exceeded.
+/// The timeout plus [`GO_TIMEOUT_GRACE`] is also enforced for the whole `go test` process
+/// (including compilation), so e.g. infinite loops in `init()` are reported as timeouts as well.
+/// The grace period leaves go time to report the hanging tests first.
 pub struct GoRunner {
     dir: PathBuf,
     timeout: Duration,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
 }
 
 impl GoRunner {
@@ -13,35 +33,260 @@ impl GoRunner {
         Self {
             dir: dir.into(),
             timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
         }
     }
 
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the sandbox (see [`TestRunner`]), none by default.
+    /// Besides the package directory, the `go.mod` and `go.sum` files
+    /// of the module the package belongs to are available in the sandbox.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
     /// Runs the go build command in the given directory.
     /// Returns the output of the command.
+    #[deprecated(
+        since = "0.1.0",
+        note = "`go test` builds the tests itself, use `TestRunner::run_tests` instead"
+    )]
     pub fn build(&self) -> Output {
-        let output = Command::new("go")
+        Command::new("go")
             .current_dir(&self.dir)
             .arg("build")
             .arg(".")
             .output()
-            .expect("Failed to run go build");
-        output
+            .expect("Failed to run go build")
+    }
+}
+
+impl TestRunner for GoRunner {
+    fn limits(&self) -> ResourceLimits {
+        self.limits
     }
 
     /// Runs the go test command in the given directory with the given timeout.
-    /// Returns the output of the command.
-    pub fn run_tests(&self) -> TestResult {
-        let timeout = format!("-timeout={}s", self.timeout.as_secs());
-        let output = Command::new("go")
-            .current_dir(&self.dir)
-            .arg("test")
-            .arg("-v")
-            .arg(timeout)
-            .arg(".")
-            .output()
-            .expect("Failed to run go test");
+    /// The `-json` flag is used to get a machine readable event stream.
+    fn run(&self) -> io::Result<RunOutput> {
+        let module_files = Language::Go.module_files(&self.dir);
+        let module_files: Vec<Bind> = module_files
+            .iter()
+            .map(|file| Bind::ReadOnly(file))
+            .collect();
+        run_sandboxed(
+            Command::new("go")
+                .current_dir(&self.dir)
+                .arg("test")
+                .arg("-json")
+                .arg(go_timeout_flag(self.timeout))
+                .arg("."),
+            self.timeout + GO_TIMEOUT_GRACE,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &module_files,
+        )
+    }
+
+    /// Parses the `-json` output of go test.
+    fn parse(&self, output: &RunOutput) -> TestReport {
+        // Older go versions write build errors to stderr, headed by the package.
+        let events = format!("{}\n{}", output.stdout_lossy(), output.stderr_lossy());
+        let mut report = TestReport::from_go_test_json(&events);
+        if output.timed_out {
+            report.passed = false;
+            report.failure = Some(TestResult::Timeout);
+        }
+        report
+    }
+}
+
+/// Test runner for all Go packages below a directory using a single `go test ./...` invocation.
+///
+/// This avoids starting the go tool for every package,
+/// which makes a difference if there are many small packages
+/// (e.g. one per student and task in the grading directory).
+///
+/// The limits apply to each test binary, as to the processes of a [`GoRunner`],
+/// and are reported per package. As all packages are tested by a single process,
+/// there is no sandbox: it couldn't keep the packages of different students apart.
+pub struct GoBatchRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    limits: ResourceLimits,
+}
+
+impl GoBatchRunner {
+    /// Creates a runner for the packages below the given directory.
+    /// The timeout applies to the tests of each package.
+    pub fn new<P: Into<PathBuf>, D: Into<Duration>>(dir: P, timeout: D) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    /// The output limit doesn't apply, as the output holds the results of all packages.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Runs the tests of all packages and returns a report per package, keyed by import path.
+    /// A package whose build failed gets a report with a build failure,
+    /// the other packages are tested regardless.
+    ///
+    /// The whole run is killed if it doesn't finish within the given total timeout.
+    /// In this case, an error is returned, as the reports of the packages
+    /// that were still running would be incomplete.
+    pub fn run_packages(
+        &self,
+        total_timeout: Duration,
+    ) -> io::Result<BTreeMap<String, TestReport>> {
+        let output = run_with_limits(
+            Command::new("go")
+                .current_dir(&self.dir)
+                .arg("test")
+                .arg("-json")
+                .arg(go_timeout_flag(self.timeout))
+                .arg("./..."),
+            total_timeout,
+            &ResourceLimits {
+                output_kb: None,
+                ..self.limits
+            },
+        )?;
+        if output.timed_out {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("go test did not finish within {:?}", total_timeout),
+            ));
+        }
+
+        Ok(self.parse_packages(&output))
+    }
+
+    /// Parses the `-json` output of `go test ./...` into a report per package.
+    /// Failed packages are classified by the configured limits like single test runs.
+    fn parse_packages(&self, output: &RunOutput) -> BTreeMap<String, TestReport> {
+        // Older go versions write build errors to stderr, headed by the package.
+        let events = format!("{}\n{}", output.stdout_lossy(), output.stderr_lossy());
+        let mut reports = TestReport::from_go_test_json_packages(&events);
+        for report in reports.values_mut() {
+            let package_output = std::iter::once(report.output.as_str())
+                .chain(report.cases.iter().map(|case| case.output.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let exceeded = exceeded_limit_in_output(&package_output, &self.limits);
+            add_exceeded_limit(report, exceeded);
+        }
+        reports
+    }
+}
+
+/// Returns the `-timeout` flag for `go test` with millisecond precision.
+/// As `-timeout=0` disables the timeout, the timeout is at least one millisecond.
+fn go_timeout_flag(timeout: Duration) -> String {
+    format!("-timeout={}ms", timeout.as_millis().max(1))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    #[test]
+    fn timeout_flag() {
+        assert_eq!(go_timeout_flag(Duration::from_secs(30)), "-timeout=30000ms");
+        assert_eq!(
+            go_timeout_flag(Duration::from_millis(250)),
+            "-timeout=250ms"
+        );
+        assert_eq!(go_timeout_flag(Duration::ZERO), "-timeout=1ms");
+    }
+
+    #[test]
+    fn killed_run_is_timeout() {
+        let runner = GoRunner::new("task_1", Duration::from_millis(500));
+        let output = RunOutput {
+            status: ExitStatus::from_raw(libc::SIGKILL),
+            stdout: br#"{"Action":"run","Test":"TestLoop"}"#.to_vec(),
+            stderr: Vec::new(),
+            timed_out: true,
+            report: None,
+        };
+
+        let report = runner.parse(&output);
+
+        assert_eq!(report.result(), TestResult::Timeout);
+        assert_eq!(report.cases.len(), 1);
+    }
+
+    #[test]
+    fn build_errors_on_stderr() {
+        let runner = GoRunner::new("task_1", Duration::from_secs(1));
+        let stdout = [
+            r#"{"Action":"start","Package":"go-exam/task_1"}"#,
+            r#"{"Action":"output","Package":"go-exam/task_1","Output":"FAIL\tgo-exam/task_1 [build failed]\n"}"#,
+            r#"{"Action":"fail","Package":"go-exam/task_1","Elapsed":0}"#,
+        ]
+        .join("\n");
+        let output = RunOutput {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: stdout.into_bytes(),
+            stderr: b"# go-exam/task_1\n./task_1.go:5:2: undefined: x\n".to_vec(),
+            timed_out: false,
+            report: None,
+        };
+
+        let report = runner.parse(&output);
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+        assert!(report.output.contains("undefined: x"));
+    }
+
+    #[test]
+    fn batch_memory_limit() {
+        let limits = ResourceLimits {
+            memory_mb: Some(256),
+            ..Default::default()
+        };
+        let runner = GoBatchRunner::new("grading", Duration::from_secs(1)).with_limits(limits);
+        let stdout = [
+            r#"{"Action":"run","Package":"m/s1/task_1","Test":"TestA"}"#,
+            r#"{"Action":"output","Package":"m/s1/task_1","Test":"TestA","Output":"fatal error: runtime: out of memory\n"}"#,
+            r#"{"Action":"fail","Package":"m/s1/task_1","Test":"TestA"}"#,
+            r#"{"Action":"fail","Package":"m/s1/task_1"}"#,
+            r#"{"Action":"run","Package":"m/s2/task_1","Test":"TestA"}"#,
+            r#"{"Action":"output","Package":"m/s2/task_1","Test":"TestA","Output":"--- FAIL: TestA\n"}"#,
+            r#"{"Action":"fail","Package":"m/s2/task_1","Test":"TestA"}"#,
+            r#"{"Action":"fail","Package":"m/s2/task_1"}"#,
+        ]
+        .join("\n");
+        let output = RunOutput {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: stdout.into_bytes(),
+            stderr: Vec::new(),
+            timed_out: false,
+            report: None,
+        };
+
+        let reports = runner.parse_packages(&output);
 
-        let output_string = String::from_utf8(output.stdout).unwrap();
-        TestResult::from_go_test_output(output_string.as_str())
+        assert_eq!(
+            reports["m/s1/task_1"].result(),
+            TestResult::MemoryLimitExceeded
+        );
+        assert_eq!(reports["m/s2/task_1"].result(), TestResult::TestFailure);
     }
 }
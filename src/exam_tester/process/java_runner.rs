@@ -0,0 +1,282 @@
+use crate::exam_tester::exam::{JUnitCase, TestOutcome, TestReport, TestResult};
+use crate::exam_tester::process::{
+    run_sandboxed, Bind, ResourceLimits, RunOutput, Sandbox, TestRunner,
+};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Directory for compiled classes, relative to the task directory.
+const CLASSES_DIR: &str = "build/classes";
+
+/// Configuration for compiling and running Java tests.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JavaConfig {
+    /// Path of the JUnit Platform Console Standalone jar.
+    pub junit_jar: PathBuf,
+    /// The JDK to use. If not set, `javac` and `java` are taken from the `PATH`.
+    pub java_home: Option<PathBuf>,
+}
+
+impl JavaConfig {
+    /// Creates a configuration using the given console launcher jar and the JDK from the `PATH`.
+    pub fn new<P: Into<PathBuf>>(junit_jar: P) -> Self {
+        Self {
+            junit_jar: junit_jar.into(),
+            java_home: None,
+        }
+    }
+
+    /// Returns the path of the given JDK tool, e.g. `javac`.
+    fn tool(&self, name: &str) -> PathBuf {
+        match &self.java_home {
+            Some(java_home) => java_home.join("bin").join(name),
+            None => PathBuf::from(name),
+        }
+    }
+}
+
+/// Test runner for Java using `javac` and the JUnit Platform Console Launcher.
+///
+/// All `.java` files in the task directory (including the grading tests)
+/// are compiled together, with the console launcher jar on the class path.
+/// The tests are then run by the console launcher, which writes an XML report.
+/// Everything is local, no network access is needed.
+pub struct JavaRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+    config: JavaConfig,
+}
+
+impl JavaRunner {
+    pub fn new<P: Into<PathBuf>, D: Into<Duration>>(
+        dir: P,
+        timeout: D,
+        config: JavaConfig,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
+            config,
+        }
+    }
+
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the sandbox (see [`TestRunner`]), none by default.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    fn class_path(&self, paths: &[&Path]) -> io::Result<std::ffi::OsString> {
+        std::env::join_paths(paths).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+}
+
+impl TestRunner for JavaRunner {
+    fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// Checks that the console launcher jar exists, which is needed to compile the tests.
+    /// Returns an error otherwise, as this is a problem of the configuration, not of the submission.
+    fn prepare(&self) -> io::Result<()> {
+        if !self.config.junit_jar.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "JUnit console launcher not found: {:?}",
+                    self.config.junit_jar
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Compiles all sources and tests with `javac`.
+    /// The compiler diagnostics are kept as the output of a failed build.
+    fn build(&self) -> io::Result<Option<TestReport>> {
+        let sources = crate::filesystem::files_with_suffix_recursive(&self.dir, ".java")
+            .map_err(io::Error::other)?;
+        let classes_dir = self.dir.join(CLASSES_DIR);
+        fs::create_dir_all(&classes_dir)?;
+
+        let output = run_sandboxed(
+            Command::new(self.config.tool("javac"))
+                .current_dir(&self.dir)
+                .arg("-d")
+                .arg(&classes_dir)
+                .arg("-cp")
+                .arg(self.class_path(&[&self.config.junit_jar])?)
+                .args(&sources),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[
+                Bind::ReadOnly(&self.config.junit_jar),
+                Bind::Writable(&self.dir),
+            ],
+        )?;
+
+        Ok(TestReport::from_build_output(&output))
+    }
+
+    /// Runs the console launcher, scanning the compiled classes for tests.
+    fn run(&self) -> io::Result<RunOutput> {
+        let report_dir = tempfile::tempdir()?;
+
+        let mut output = run_sandboxed(
+            Command::new(self.config.tool("java"))
+                .current_dir(&self.dir)
+                .arg("-jar")
+                .arg(&self.config.junit_jar)
+                .arg("execute")
+                .arg("--disable-banner")
+                .arg("--class-path")
+                .arg(self.dir.join(CLASSES_DIR))
+                .arg("--scan-class-path")
+                .arg("--reports-dir")
+                .arg(report_dir.path()),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[
+                Bind::ReadOnly(&self.config.junit_jar),
+                Bind::Writable(report_dir.path()),
+            ],
+        )?;
+        output.report = fs::read_to_string(report_dir.path().join("TEST-junit-jupiter.xml")).ok();
+        Ok(output)
+    }
+
+    /// Parses the XML report of the console launcher.
+    fn parse(&self, output: &RunOutput) -> TestReport {
+        let cases = output.report.as_deref().map(JUnitCase::parse_all);
+        let mut report = match cases {
+            Some(Ok(cases)) => TestReport::from_junit_cases(&cases, junit_outcome),
+            // Without a report, the tests didn't run, e.g. because the JVM crashed.
+            _ => TestReport {
+                failure: Some(TestResult::Crash),
+                ..Default::default()
+            },
+        };
+        report.output.push_str(&output.stdout_lossy());
+        report.output.push_str(&output.stderr_lossy());
+        if output.timed_out {
+            report.failure = Some(TestResult::Timeout);
+        }
+        report.passed &= output.status.success();
+        report
+    }
+}
+
+/// Classifies a JUnit test case.
+/// A `StackOverflowError` is a stack overflow, other uncaught exceptions are crashes
+/// and failed assertions are test failures.
+fn junit_outcome(case: &JUnitCase) -> TestOutcome {
+    match case.problem() {
+        Some(problem) if problem.mentions("java.lang.StackOverflowError") => {
+            TestOutcome::StackOverflow
+        }
+        _ => case.default_outcome(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn parse(code: i32, report: Option<&str>) -> TestReport {
+        let output = RunOutput {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            timed_out: false,
+            report: report.map(str::to_string),
+        };
+        JavaRunner::new(".", Duration::from_secs(1), JavaConfig::new("junit.jar")).parse(&output)
+    }
+
+    #[test]
+    fn classification() {
+        let report = parse(
+            1,
+            Some(
+                r#"<testsuite name="JUnit Jupiter">
+                    <testcase name="adds()" classname="TaskTest" time="0.01">
+                        <failure message="expected: &lt;2&gt; but was: &lt;0&gt;" type="org.opentest4j.AssertionFailedError">trace</failure>
+                    </testcase>
+                    <testcase name="recurses()" classname="TaskTest" time="0.2">
+                        <error type="java.lang.StackOverflowError">java.lang.StackOverflowError
+	at Task.rec(Task.java:3)</error>
+                    </testcase>
+                    <testcase name="parses()" classname="TaskTest" time="0.01">
+                        <error message="boom" type="java.lang.IllegalStateException">trace</error>
+                    </testcase>
+                </testsuite>"#,
+            ),
+        );
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["TaskTest.adds()"]
+        );
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::StackOverflow),
+            vec!["TaskTest.recurses()"]
+        );
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Crashed),
+            vec!["TaskTest.parses()"]
+        );
+        assert_eq!(report.result(), TestResult::StackOverflow);
+    }
+
+    /// A missing console launcher is an error, not a build failure of the submission.
+    #[test]
+    fn missing_junit_jar() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Task.java"), "class Task {}").unwrap();
+        let runner = JavaRunner::new(
+            dir.path(),
+            Duration::from_secs(10),
+            JavaConfig::new(dir.path().join("missing.jar")),
+        );
+
+        let error = runner.run_tests().unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(!dir.path().join(CLASSES_DIR).exists());
+    }
+
+    #[test]
+    fn missing_report() {
+        let report = parse(1, None);
+
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn tool_paths() {
+        let mut config = JavaConfig::new("junit.jar");
+        assert_eq!(config.tool("javac"), PathBuf::from("javac"));
+
+        config.java_home = Some(PathBuf::from("/opt/jdk"));
+        assert_eq!(config.tool("javac"), PathBuf::from("/opt/jdk/bin/javac"));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::exam_tester::process::{
+    CargoRunner, CommandConfig, CommandRunner, CppConfig, CppRunner, GoRunner, JavaConfig,
+    JavaRunner, PytestRunner, ReportFormat, ResourceLimits, Sandbox, TestRunner,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The programming languages (and thus test runners) supported for tasks.
+///
+/// In configuration files, the language is given by the `language` key
+/// next to the language specific configuration, e.g.
+/// `{ language = "java", junit_jar = "lib/junit.jar" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "language", rename_all = "lowercase")]
+pub enum Language {
+    /// Go, tested with `go test`.
+    Go,
+    /// Rust, tested with `cargo test`.
+    Rust,
+    /// Python, tested with pytest.
+    Python,
+    /// C or C++, built with the configured build command and tested with Catch2.
+    Cpp(CppConfig),
+    /// Java, compiled with `javac` and tested with the JUnit Platform Console Launcher.
+    Java(JavaConfig),
+    /// Any other language, built and tested with configured commands.
+    Command(CommandConfig),
+}
+
+impl Language {
+    /// Detects the language of a task from the files in the given task directory.
+    /// Returns `None` if no known language could be detected.
+    ///
+    /// Directories with C sources only are not detected, as the default C/C++ configurations
+    /// compile C++ sources and Catch2 tests are C++. Such tasks need the language
+    /// and build command in their configuration.
+    pub fn detect(task_dir: &Path) -> Option<Self> {
+        let has_suffix = |suffix| {
+            crate::filesystem::files_with_suffix(task_dir, suffix)
+                .map(|files| !files.is_empty())
+                .unwrap_or(false)
+        };
+
+        if task_dir.join("Cargo.toml").is_file() {
+            Some(Language::Rust)
+        } else if task_dir.join("go.mod").is_file() || has_suffix(".go") {
+            Some(Language::Go)
+        } else if has_suffix(".py") {
+            Some(Language::Python)
+        } else if task_dir.join("CMakeLists.txt").is_file() {
+            Some(Language::Cpp(CppConfig::cmake()))
+        } else if has_suffix(".cpp") {
+            Some(Language::Cpp(CppConfig::gpp()))
+        } else if has_suffix(".java") {
+            Some(Language::Java(JavaConfig::default()))
+        } else {
+            None
+        }
+    }
+
+    /// Creates a test runner for this language working in the given directory,
+    /// running the tests with the given timeout and resource limits,
+    /// in the given sandbox if there is one.
+    pub fn runner<P: Into<PathBuf>>(
+        &self,
+        dir: P,
+        timeout: Duration,
+        limits: ResourceLimits,
+        sandbox: Option<&Sandbox>,
+    ) -> Box<dyn TestRunner> {
+        let sandbox = sandbox.cloned();
+        match self {
+            Language::Go => Box::new(
+                GoRunner::new(dir, timeout)
+                    .with_limits(limits)
+                    .with_sandbox(sandbox),
+            ),
+            Language::Rust => Box::new(
+                CargoRunner::new(dir, timeout)
+                    .with_limits(limits)
+                    .with_sandbox(sandbox),
+            ),
+            Language::Python => Box::new(
+                PytestRunner::new(dir, timeout)
+                    .with_limits(limits)
+                    .with_sandbox(sandbox),
+            ),
+            Language::Cpp(config) => Box::new(
+                CppRunner::new(dir, timeout)
+                    .with_config(config.clone())
+                    .with_limits(limits)
+                    .with_sandbox(sandbox),
+            ),
+            Language::Java(config) => Box::new(
+                JavaRunner::new(dir, timeout, config.clone())
+                    .with_limits(limits)
+                    .with_sandbox(sandbox),
+            ),
+            Language::Command(config) => Box::new(
+                CommandRunner::new(dir, timeout, config.clone())
+                    .with_limits(limits)
+                    .with_sandbox(sandbox),
+            ),
+        }
+    }
+
+    /// Returns the patterns of test files relative to the task directory.
+    /// Patterns may start with a fixed subdirectory (e.g. `tests/*.rs`)
+    /// and use `*` as a wildcard in the file name.
+    pub fn test_file_patterns(&self) -> Vec<String> {
+        let patterns: &[&str] = match self {
+            Language::Go => &["*_test.go"],
+            Language::Rust => &["tests/*.rs"],
+            Language::Python => &["test_*.py", "*_test.py"],
+            Language::Cpp(_) => &["*_test.cpp", "test_*.cpp"],
+            Language::Java(_) => &["*Test.java"],
+            Language::Command(config) => return config.test_patterns.clone(),
+        };
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    /// Returns the patterns of the grading tests used if a task doesn't configure them,
+    /// i.e. the tests that are not handed out with the assignment.
+    /// Only Go has such a convention (`*_grading_test.go`).
+    pub fn grading_test_patterns(&self) -> Vec<String> {
+        match self {
+            Language::Go => vec!["*_grading_test.go".to_string()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the name of the source file for the given task,
+    /// i.e. the file the test results are appended to.
+    pub fn source_file(&self, task_name: &str) -> String {
+        match self {
+            Language::Go => format!("{}.go", task_name),
+            Language::Rust => "src/lib.rs".to_string(),
+            Language::Python => format!("{}.py", task_name),
+            Language::Cpp(_) => format!("{}.cpp", task_name),
+            Language::Java(_) => format!("{}.java", task_name),
+            Language::Command(config) => config.source_file.replace("{task}", task_name),
+        }
+    }
+
+    /// Returns the files of the module (or project) the package in the given directory
+    /// belongs to, if the module's root directory is an ancestor of the package directory.
+    /// For Go, these are the `go.mod` and `go.sum` files of the module,
+    /// which determine the import path of the package.
+    /// Other languages don't use such files outside the task directory.
+    pub fn module_files(&self, package_dir: &Path) -> Vec<PathBuf> {
+        if *self != Language::Go || package_dir.join("go.mod").is_file() {
+            return Vec::new();
+        }
+        let Some(module_dir) = package_dir
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join("go.mod").is_file())
+        else {
+            return Vec::new();
+        };
+        ["go.mod", "go.sum"]
+            .iter()
+            .map(|file| module_dir.join(file))
+            .filter(|file| file.is_file())
+            .collect()
+    }
+
+    /// Returns whether the test runner reports the individual tests,
+    /// i.e. whether a report without tests means that no tests were run.
+    /// This is not the case for commands that only report the result by their exit code.
+    pub fn reports_tests(&self) -> bool {
+        match self {
+            Language::Command(config) => config.report_format != ReportFormat::ExitCode,
+            _ => true,
+        }
+    }
+
+    /// Returns the prefix for line comments in source files.
+    pub fn comment_prefix(&self) -> &str {
+        match self {
+            Language::Go | Language::Rust | Language::Cpp(_) | Language::Java(_) => "//",
+            Language::Python => "#",
+            Language::Command(config) => &config.comment_prefix,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_testdata_go_exam() {
+        let base_dir = std::env::current_dir().unwrap();
+        let task_dir = base_dir.join("testdata/go-exam/aufgaben/task_1");
+
+        assert_eq!(Language::detect(&task_dir), Some(Language::Go));
+    }
+
+    #[test]
+    fn detect_rust() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("Cargo.toml"), "").unwrap();
+
+        assert_eq!(Language::detect(temp_dir.path()), Some(Language::Rust));
+    }
+
+    #[test]
+    fn detect_python() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("task_1.py"), "").unwrap();
+
+        assert_eq!(Language::detect(temp_dir.path()), Some(Language::Python));
+    }
+
+    #[test]
+    fn detect_cpp() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("task_1.c"), "").unwrap();
+        assert_eq!(Language::detect(temp_dir.path()), None);
+
+        std::fs::write(temp_dir.path().join("task_1_test.cpp"), "").unwrap();
+        assert_eq!(
+            Language::detect(temp_dir.path()),
+            Some(Language::Cpp(CppConfig::gpp()))
+        );
+
+        std::fs::write(temp_dir.path().join("CMakeLists.txt"), "").unwrap();
+        assert_eq!(
+            Language::detect(temp_dir.path()),
+            Some(Language::Cpp(CppConfig::cmake()))
+        );
+    }
+
+    #[test]
+    fn detect_unknown() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+
+        assert_eq!(Language::detect(temp_dir.path()), None);
+    }
+
+    #[test]
+    fn go_module_files() {
+        let module_dir = tempfile::tempdir().unwrap();
+        let package_dir = module_dir.path().join("student_1/task_1");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(module_dir.path().join("go.mod"), "module grading\n").unwrap();
+
+        assert_eq!(
+            Language::Go.module_files(&package_dir),
+            vec![module_dir.path().join("go.mod")]
+        );
+        assert_eq!(
+            Language::Go.module_files(module_dir.path()),
+            Vec::<PathBuf>::new()
+        );
+        assert_eq!(
+            Language::Python.module_files(&package_dir),
+            Vec::<PathBuf>::new()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Deserialize;
+use std::process::Command;
+
+/// Resource limits for the processes of a test run.
+///
+/// The limits are applied as rlimits to the test process and inherited by
+/// all processes it starts. Note the following caveats of rlimits:
+/// * The memory limit bounds the address space, not the resident memory.
+///   Runtimes reserving large amounts of address space up front (e.g. the JVM)
+///   might not start with low limits.
+/// * The process limit counts all processes of the user running the tests,
+///   not only those of the test run. Running the tests as a dedicated user is recommended.
+///   It doesn't apply to root.
+/// * The CPU time limit applies to each process separately.
+///
+/// In configuration files, the limits are given in a `[limits]` table, e.g.
+/// `memory_mb = 1024`, `cpu_seconds = 60`, `processes = 256`,
+/// `file_size_mb = 64` and `output_kb = 1024`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// Maximum size of the address space of each process in MiB.
+    pub memory_mb: Option<u64>,
+    /// Maximum CPU time of each process in seconds.
+    pub cpu_seconds: Option<u64>,
+    /// Maximum number of processes of the user.
+    pub processes: Option<u64>,
+    /// Maximum size of files written in MiB.
+    pub file_size_mb: Option<u64>,
+    /// Maximum size of the captured stdout and stderr (each) in KiB.
+    /// Any further output is discarded.
+    pub output_kb: Option<u64>,
+}
+
+const MIB: u64 = 1024 * 1024;
+
+impl ResourceLimits {
+    /// Returns these limits with the limits that are not set taken from the given limits.
+    pub fn or(self, other: ResourceLimits) -> ResourceLimits {
+        ResourceLimits {
+            memory_mb: self.memory_mb.or(other.memory_mb),
+            cpu_seconds: self.cpu_seconds.or(other.cpu_seconds),
+            processes: self.processes.or(other.processes),
+            file_size_mb: self.file_size_mb.or(other.file_size_mb),
+            output_kb: self.output_kb.or(other.output_kb),
+        }
+    }
+
+    /// Returns the maximum number of bytes captured from stdout and stderr (each), if limited.
+    pub fn output_bytes(&self) -> Option<usize> {
+        self.output_kb
+            .map(|kb| usize::try_from(kb.saturating_mul(1024)).unwrap_or(usize::MAX))
+    }
+
+    /// Sets up the given command to run with the process limits.
+    /// Does nothing on platforms without rlimits.
+    pub fn apply(&self, command: &mut Command) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            let limits = self.rlimits();
+            if limits.is_empty() {
+                return;
+            }
+            // Only calls setrlimit, which is async-signal-safe, between fork and exec.
+            unsafe {
+                command.pre_exec(move || {
+                    for &(resource, value) in &limits {
+                        let limit = libc::rlimit {
+                            rlim_cur: value,
+                            rlim_max: value,
+                        };
+                        if libc::setrlimit(resource, &limit) != 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                    }
+                    Ok(())
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = command;
+        }
+    }
+
+    /// Returns the rlimits to set as resources and values.
+    #[cfg(unix)]
+    fn rlimits(&self) -> Vec<(RlimitResource, libc::rlim_t)> {
+        let to_rlim = |value: u64| libc::rlim_t::try_from(value).unwrap_or(libc::RLIM_INFINITY);
+        let limits = [
+            (
+                libc::RLIMIT_AS,
+                self.memory_mb.map(|mb| mb.saturating_mul(MIB)),
+            ),
+            (libc::RLIMIT_CPU, self.cpu_seconds),
+            (libc::RLIMIT_NPROC, self.processes),
+            (
+                libc::RLIMIT_FSIZE,
+                self.file_size_mb.map(|mb| mb.saturating_mul(MIB)),
+            ),
+        ];
+        limits
+            .into_iter()
+            .filter_map(|(resource, value)| Some((resource, to_rlim(value?))))
+            .collect()
+    }
+}
+
+#[cfg(all(unix, target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::exam_tester::process::run_with_limits;
+    use std::time::Duration;
+
+    #[test]
+    fn or() {
+        let task = ResourceLimits {
+            memory_mb: Some(256),
+            ..Default::default()
+        };
+        let exam = ResourceLimits {
+            memory_mb: Some(1024),
+            cpu_seconds: Some(10),
+            ..Default::default()
+        };
+
+        let limits = task.or(exam);
+
+        assert_eq!(limits.memory_mb, Some(256));
+        assert_eq!(limits.cpu_seconds, Some(10));
+        assert_eq!(limits.processes, None);
+    }
+
+    #[test]
+    fn limits_are_applied() {
+        let limits = ResourceLimits {
+            cpu_seconds: Some(7),
+            file_size_mb: Some(1),
+            ..Default::default()
+        };
+
+        let output = run_with_limits(
+            Command::new("sh").arg("-c").arg("ulimit -t; ulimit -f"),
+            Duration::from_secs(10),
+            &limits,
+        )
+        .unwrap();
+
+        // `ulimit -f` reports blocks of 512 bytes.
+        assert_eq!(output.stdout_lossy(), "7\n2048\n");
+    }
+
+    #[test]
+    fn file_size_exceeded() {
+        let dir = tempfile::tempdir().unwrap();
+        let limits = ResourceLimits {
+            file_size_mb: Some(1),
+            ..Default::default()
+        };
+
+        let output = run_with_limits(
+            Command::new("sh")
+                .current_dir(dir.path())
+                .arg("-c")
+                .arg("exec head -c 2000000 /dev/zero > big"),
+            Duration::from_secs(10),
+            &limits,
+        )
+        .unwrap();
+
+        assert_eq!(output.signal(), Some(libc::SIGXFSZ));
+    }
+}
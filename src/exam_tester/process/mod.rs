@@ -1,3 +1,23 @@
+mod cargo_runner;
+mod command_runner;
+mod cpp_runner;
+mod execution;
 mod go_runner;
+mod java_runner;
+mod language;
+mod limits;
+mod pytest_runner;
+mod sandbox;
+mod test_runner;
 
-pub use go_runner::GoRunner;
+pub use cargo_runner::CargoRunner;
+pub use command_runner::{CommandConfig, CommandRunner, ReportFormat, REPORT_PLACEHOLDER};
+pub use cpp_runner::{CppConfig, CppRunner};
+pub use execution::{run_sandboxed, run_with_limits, run_with_timeout, RunOutput};
+pub use go_runner::{GoBatchRunner, GoRunner, GO_TIMEOUT_GRACE};
+pub use java_runner::{JavaConfig, JavaRunner};
+pub use language::Language;
+pub use limits::ResourceLimits;
+pub use pytest_runner::PytestRunner;
+pub use sandbox::{Bind, Sandbox};
+pub use test_runner::TestRunner;
@@ -0,0 +1,300 @@
+use crate::exam_tester::exam::{
+    JUnitCase, JUnitProblem, JUnitResult, TestOutcome, TestReport, TestResult,
+};
+use crate::exam_tester::process::{
+    run_sandboxed, Bind, ResourceLimits, RunOutput, Sandbox, TestRunner,
+};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Test runner for Python using pytest.
+///
+/// Runs `python3 -m pytest` in the directory and reads the results
+/// from a JUnit XML report written to a temporary file.
+/// Bytecode and cache files are not written, so the directory isn't cluttered.
+pub struct PytestRunner {
+    dir: PathBuf,
+    timeout: Duration,
+    limits: ResourceLimits,
+    sandbox: Option<Sandbox>,
+    python: String,
+}
+
+impl PytestRunner {
+    pub fn new<P: Into<PathBuf>, D: Into<Duration>>(dir: P, timeout: D) -> Self {
+        Self {
+            dir: dir.into(),
+            timeout: timeout.into(),
+            limits: ResourceLimits::default(),
+            sandbox: None,
+            python: "python3".to_string(),
+        }
+    }
+
+    /// Sets the resource limits (see [`TestRunner`]), no limits by default.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the sandbox (see [`TestRunner`]), none by default.
+    pub fn with_sandbox(mut self, sandbox: Option<Sandbox>) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Sets the python interpreter to use, `python3` by default.
+    pub fn with_python<S: Into<String>>(mut self, python: S) -> Self {
+        self.python = python.into();
+        self
+    }
+}
+
+impl TestRunner for PytestRunner {
+    fn limits(&self) -> ResourceLimits {
+        self.limits
+    }
+
+    /// Runs pytest, killing it if it exceeds the runner's timeout.
+    /// Returns an error if pytest is not installed for the interpreter.
+    fn run(&self) -> io::Result<RunOutput> {
+        let report_dir = tempfile::tempdir()?;
+        let report_file = report_dir.path().join("report.xml");
+
+        let mut output = run_sandboxed(
+            Command::new(&self.python)
+                .current_dir(&self.dir)
+                .env("PYTHONDONTWRITEBYTECODE", "1")
+                .arg("-m")
+                .arg("pytest")
+                .arg("-p")
+                .arg("no:cacheprovider")
+                .arg(format!("--junitxml={}", report_file.display())),
+            self.timeout,
+            &self.limits,
+            self.sandbox.as_ref(),
+            &[Bind::Writable(report_dir.path())],
+        )?;
+        output.report = fs::read_to_string(&report_file).ok();
+
+        if output.report.is_none() && output.stderr_lossy().contains("No module named pytest") {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("pytest is not installed for {}", self.python),
+            ));
+        }
+        Ok(output)
+    }
+
+    /// Parses the JUnit XML report written by pytest.
+    /// The console output of pytest is added to the report's output.
+    fn parse(&self, output: &RunOutput) -> TestReport {
+        let cases = output.report.as_deref().map(JUnitCase::parse_all);
+        let mut report = match cases {
+            Some(Ok(cases)) => {
+                let mut report = TestReport::from_junit_cases(&cases, pytest_outcome);
+                report.failure = collection_failure(&cases);
+                report
+            }
+            // Without a report, pytest didn't get to run the tests at all.
+            _ => TestReport {
+                failure: Some(TestResult::Crash),
+                ..Default::default()
+            },
+        };
+
+        report.output.push_str(&output.stdout_lossy());
+        report.output.push_str(&output.stderr_lossy());
+        if output.timed_out {
+            report.failure = Some(TestResult::Timeout);
+        }
+        report.passed &= output.status.success();
+        report
+    }
+}
+
+/// Classifies a pytest test case.
+/// Failed assertions are test failures, a `RecursionError` is a stack overflow
+/// and any other exception is a crash.
+fn pytest_outcome(case: &JUnitCase) -> TestOutcome {
+    match (&case.result, case.problem()) {
+        (_, Some(problem)) if problem.mentions("RecursionError") => TestOutcome::StackOverflow,
+        (JUnitResult::Failure(problem), _) if is_assertion(problem) => TestOutcome::Failed,
+        (_, Some(_)) => TestOutcome::Crashed,
+        (_, None) => case.default_outcome(),
+    }
+}
+
+/// Checks whether the problem was caused by a failed assertion.
+/// Pytest ends the text of a failure with the location and the exception type,
+/// e.g. `test_task.py:5: AssertionError`.
+fn is_assertion(problem: &JUnitProblem) -> bool {
+    let exception = problem
+        .text
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| line.rsplit(": ").next());
+    match exception {
+        Some(exception) => exception.trim() == "AssertionError",
+        None => problem
+            .message
+            .as_deref()
+            .is_some_and(|message| message.starts_with("assert")),
+    }
+}
+
+/// Returns the failure of the whole run if collecting the tests failed.
+/// Syntax and import errors are build failures, anything else
+/// (e.g. an exception in module level code) is a crash.
+fn collection_failure(cases: &[JUnitCase]) -> Option<TestResult> {
+    let problems: Vec<&JUnitProblem> = cases
+        .iter()
+        .filter_map(|case| match &case.result {
+            JUnitResult::Error(problem)
+                if problem.message.as_deref() == Some("collection failure") =>
+            {
+                Some(problem)
+            }
+            _ => None,
+        })
+        .collect();
+
+    let is_build_error = |problem: &&JUnitProblem| {
+        [
+            "SyntaxError",
+            "IndentationError",
+            "ImportError",
+            "ModuleNotFoundError",
+        ]
+        .iter()
+        .any(|error| problem.mentions(error))
+    };
+
+    if problems.is_empty() {
+        None
+    } else if problems.iter().any(is_build_error) {
+        Some(TestResult::BuildFailure)
+    } else {
+        Some(TestResult::Crash)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::ExitStatus;
+
+    fn exit_status(code: i32) -> ExitStatus {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(code << 8)
+    }
+
+    fn run_output(code: i32, report: &str) -> RunOutput {
+        RunOutput {
+            status: exit_status(code),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            timed_out: false,
+            report: Some(report.to_string()),
+        }
+    }
+
+    fn parse(code: i32, report: &str) -> TestReport {
+        PytestRunner::new(".", Duration::from_secs(1)).parse(&run_output(code, report))
+    }
+
+    #[test]
+    fn passed() {
+        let report = parse(
+            0,
+            r#"<testsuites><testsuite name="pytest" tests="1">
+                <testcase classname="test_task" name="test_add" time="0.001"/>
+            </testsuite></testsuites>"#,
+        );
+
+        assert_eq!(report.result(), TestResult::Success);
+        assert_eq!(report.cases[0].name, "test_task.test_add");
+    }
+
+    #[test]
+    fn assertion_and_exception() {
+        let report = parse(
+            1,
+            r#"<testsuites><testsuite name="pytest" tests="2" failures="2">
+                <testcase classname="test_task" name="test_assert" time="0.001">
+                    <failure message="assert 1 == 2">def test_assert():
+&gt;       assert add(1, 0) == 2
+E       assert 1 == 2
+
+test_task.py:5: AssertionError</failure>
+                </testcase>
+                <testcase classname="test_task" name="test_div" time="0.001">
+                    <failure message="ZeroDivisionError: division by zero">task.py:3: in div
+    return a / b
+E   ZeroDivisionError: division by zero
+
+task.py:3: ZeroDivisionError</failure>
+                </testcase>
+            </testsuite></testsuites>"#,
+        );
+
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Failed),
+            vec!["test_task.test_assert"]
+        );
+        assert_eq!(
+            report.tests_with_outcome(TestOutcome::Crashed),
+            vec!["test_task.test_div"]
+        );
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+
+    #[test]
+    fn recursion_error() {
+        let report = parse(
+            1,
+            r#"<testsuite>
+                <testcase classname="test_task" name="test_rec">
+                    <failure message="RecursionError: maximum recursion depth exceeded">task.py:2: RecursionError</failure>
+                </testcase>
+            </testsuite>"#,
+        );
+
+        assert_eq!(report.result(), TestResult::StackOverflow);
+    }
+
+    #[test]
+    fn syntax_error_in_collection() {
+        let report = parse(
+            2,
+            r#"<testsuite>
+                <testcase classname="" name="test_task">
+                    <error message="collection failure">E     File "task.py", line 1
+E       def add(a, b)
+E                    ^
+E   SyntaxError: expected ':'</error>
+                </testcase>
+            </testsuite>"#,
+        );
+
+        assert_eq!(report.result(), TestResult::BuildFailure);
+    }
+
+    #[test]
+    fn exception_in_collection() {
+        let report = parse(
+            2,
+            r#"<testsuite>
+                <testcase classname="" name="test_task">
+                    <error message="collection failure">E   KeyError: 'x'</error>
+                </testcase>
+            </testsuite>"#,
+        );
+
+        assert_eq!(report.result(), TestResult::Crash);
+    }
+}
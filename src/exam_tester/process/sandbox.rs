@@ -0,0 +1,231 @@
+use crate::Error;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directories of the toolchains mounted read-only into the sandbox, if they exist.
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib32", "/lib64", "/etc"];
+
+/// Sandbox for test runs using [bubblewrap](https://github.com/containers/bubblewrap) (`bwrap`).
+///
+/// Test processes run in new namespaces without network access and only see
+/// * the system directories (`/usr`, `/lib`, `/etc`, ...) and the directory
+///   of the test command's toolchain, read-only,
+/// * the additionally configured directories,
+/// * the task directory (the command's current directory) with a temporary overlay,
+///   i.e. writes succeed, but are discarded after the run,
+/// * a temporary `/tmp`, which is also used as the home directory.
+///
+/// So students cannot read other solutions, modify the grading tests or
+/// leave files behind. Caches of the toolchains (e.g. the Go build cache)
+/// start empty in every run, unless their directories are configured as writable.
+/// Separate build steps (e.g. `javac` or a configured build command) run in the sandbox
+/// as well, but with the task directory writable, so the tests can use the build results.
+///
+/// Commands terminated by a signal in the sandbox are reported with exit code 128 + signal,
+/// see [`run_sandboxed`](crate::exam_tester::process::run_sandboxed).
+///
+/// Overlays require bubblewrap 0.8 or newer and unprivileged user namespaces.
+/// In configuration files, the sandbox is enabled by a `[sandbox]` table, e.g.
+/// `read_only = ["/opt/jdk"]` and `writable = ["/var/cache/go-build"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sandbox {
+    /// The bubblewrap executable.
+    pub bwrap: PathBuf,
+    /// Additional directories mounted read-only, e.g. toolchains outside the system directories.
+    pub read_only: Vec<PathBuf>,
+    /// Additional directories mounted writable, shared by all test runs.
+    pub writable: Vec<PathBuf>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            bwrap: PathBuf::from("bwrap"),
+            read_only: Vec::new(),
+            writable: Vec::new(),
+        }
+    }
+}
+
+/// An additional path made available in the sandbox for a single command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bind<'a> {
+    /// A file or directory mounted read-only, e.g. the `go.mod` of the module.
+    ReadOnly(&'a Path),
+    /// A directory mounted writable, e.g. the directory a test report is written to.
+    Writable(&'a Path),
+}
+
+impl Sandbox {
+    /// Checks that the sandbox can be used, i.e. that bubblewrap is installed
+    /// and able to set up a sandbox for a task directory.
+    pub fn check(&self) -> Result<(), Error> {
+        let task_dir = tempfile::tempdir()
+            .map_err(|e| Error::io("create directory in", std::env::temp_dir(), e))?;
+        let mut command = Command::new("true");
+        command.current_dir(task_dir.path());
+        let output = self.command(&command, &[]).output().map_err(|e| {
+            let message = if e.kind() == io::ErrorKind::NotFound {
+                format!(
+                    "the sandbox requires bubblewrap, but {:?} was not found. \
+                    Install bubblewrap (e.g. the `bubblewrap` package) or disable the sandbox",
+                    self.bwrap
+                )
+            } else {
+                format!("failed to start {:?}: {}", self.bwrap, e)
+            };
+            Error::Sandbox { message }
+        })?;
+        if !output.status.success() {
+            let message = format!(
+                "the sandbox cannot be set up (bubblewrap 0.8 or newer \
+                with unprivileged user namespaces is required): {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return Err(Error::Sandbox { message });
+        }
+        Ok(())
+    }
+
+    /// Returns a command running the given command in the sandbox.
+    /// The current directory of the given command is the task directory,
+    /// its environment variables are passed on.
+    pub fn command(&self, command: &Command, binds: &[Bind]) -> Command {
+        let mut sandboxed = Command::new(&self.bwrap);
+        sandboxed
+            .args(["--unshare-all", "--die-with-parent", "--new-session"])
+            .args(["--proc", "/proc", "--dev", "/dev"]);
+        for dir in SYSTEM_DIRS {
+            sandboxed.arg("--ro-bind-try").arg(dir).arg(dir);
+        }
+        if let Some(dir) = toolchain_dir(command.get_program()) {
+            sandboxed.arg("--ro-bind-try").arg(&dir).arg(&dir);
+        }
+        // Mounted after /tmp, so paths below /tmp remain visible.
+        sandboxed.args(["--tmpfs", "/tmp", "--setenv", "HOME", "/tmp"]);
+        for dir in &self.read_only {
+            sandboxed.arg("--ro-bind-try").arg(dir).arg(dir);
+        }
+        for dir in &self.writable {
+            sandboxed.arg("--bind-try").arg(dir).arg(dir);
+        }
+        if let Some(dir) = command.get_current_dir() {
+            sandboxed
+                .arg("--overlay-src")
+                .arg(dir)
+                .arg("--tmp-overlay")
+                .arg(dir)
+                .arg("--chdir")
+                .arg(dir);
+        }
+        for bind in binds {
+            match bind {
+                Bind::ReadOnly(path) => sandboxed.arg("--ro-bind").arg(path).arg(path),
+                Bind::Writable(path) => sandboxed.arg("--bind").arg(path).arg(path),
+            };
+        }
+
+        sandboxed
+            .arg("--")
+            .arg(command.get_program())
+            .args(command.get_args());
+        for (key, value) in command.get_envs() {
+            match value {
+                Some(value) => sandboxed.env(key, value),
+                None => sandboxed.env_remove(key),
+            };
+        }
+        sandboxed
+    }
+}
+
+/// Returns the installation directory of the given program if it is not a system directory,
+/// e.g. `/usr/local/go` for `/usr/local/go/bin/go`.
+/// Programs are looked up in `PATH` unless given as a path.
+fn toolchain_dir(program: &OsStr) -> Option<PathBuf> {
+    let program = Path::new(program);
+    let path = if program.components().count() > 1 {
+        program.to_path_buf()
+    } else {
+        std::env::split_paths(&std::env::var_os("PATH")?)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())?
+    };
+    let dir = path.canonicalize().ok()?.parent()?.parent()?.to_path_buf();
+    let is_system_dir = SYSTEM_DIRS
+        .iter()
+        .any(|system_dir| dir.starts_with(system_dir));
+    (!is_system_dir && dir != Path::new("/")).then_some(dir)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn sandboxed_command() {
+        let sandbox = Sandbox {
+            read_only: vec![PathBuf::from("/opt/jdk")],
+            ..Default::default()
+        };
+        let mut command = Command::new("sh");
+        command
+            .current_dir("/grading/student_1/task_1")
+            .arg("-c")
+            .arg("./test")
+            .env("PYTHONDONTWRITEBYTECODE", "1");
+
+        let sandboxed = sandbox.command(&command, &[Bind::Writable(Path::new("/tmp/report"))]);
+
+        assert_eq!(sandboxed.get_program(), "bwrap");
+        let args = args(&sandboxed).join(" ");
+        assert!(args.starts_with("--unshare-all "), "{}", args);
+        assert!(args.contains("--ro-bind-try /usr /usr"), "{}", args);
+        assert!(args.contains("--ro-bind-try /opt/jdk /opt/jdk"), "{}", args);
+        assert!(
+            args.ends_with(
+                "--overlay-src /grading/student_1/task_1 --tmp-overlay /grading/student_1/task_1 \
+                --chdir /grading/student_1/task_1 --bind /tmp/report /tmp/report -- sh -c ./test"
+            ),
+            "{}",
+            args
+        );
+        assert!(sandboxed
+            .get_envs()
+            .any(|(key, value)| key == "PYTHONDONTWRITEBYTECODE" && value == Some("1".as_ref())));
+    }
+
+    #[test]
+    fn toolchain_outside_system_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        std::fs::create_dir(dir.join("bin")).unwrap();
+        std::fs::write(dir.join("bin/go"), "").unwrap();
+
+        assert_eq!(toolchain_dir(dir.join("bin/go").as_os_str()), Some(dir));
+        assert_eq!(toolchain_dir("/bin/sh".as_ref()), None);
+    }
+
+    #[test]
+    fn missing_bwrap() {
+        let sandbox = Sandbox {
+            bwrap: PathBuf::from("/nonexistent/bwrap"),
+            ..Default::default()
+        };
+
+        let error = sandbox.check().unwrap_err().to_string();
+
+        assert!(error.contains("requires bubblewrap"), "{}", error);
+    }
+}
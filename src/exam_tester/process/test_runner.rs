@@ -0,0 +1,307 @@
+use crate::exam_tester::exam::{TestReport, TestResult};
+use crate::exam_tester::process::{ResourceLimits, RunOutput};
+use std::io;
+
+/// Adapter for running the tests of a single task with a language specific toolchain.
+///
+/// A runner works on a single directory containing a student's submission
+/// together with the grading tests.
+/// Running the tests is split into several steps which are executed in order by
+/// [`TestRunner::run_tests`]: preparing the directory, building, running the tests
+/// and parsing their output into the shared result model.
+///
+/// Runners are set up with resource limits (see [`ResourceLimits`]) and optionally a sandbox
+/// (see [`Sandbox`](crate::exam_tester::process::Sandbox)) by their `with_limits`
+/// and `with_sandbox` methods. Both apply to every process started for the submission,
+/// i.e. to the build step as well as to the test run.
+///
+/// Errors are only returned if the runner itself fails, e.g. if the toolchain
+/// cannot be started. Failures of the submission (e.g. build failures)
+/// are regular results and reported via the returned [`TestReport`].
+pub trait TestRunner {
+    /// Prepares the directory for the test run.
+    /// The default implementation does nothing.
+    fn prepare(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Builds the submission together with the tests.
+    /// Returns a report if the build failed and no tests can be run.
+    /// The default implementation does nothing, which is suitable for runners
+    /// where running the tests includes building them.
+    fn build(&self) -> io::Result<Option<TestReport>> {
+        Ok(None)
+    }
+
+    /// Runs the tests and returns the output of the test process.
+    fn run(&self) -> io::Result<RunOutput>;
+
+    /// Parses the output of the test process into a report.
+    fn parse(&self, output: &RunOutput) -> TestReport;
+
+    /// Returns the resource limits the tests are run with.
+    /// Failed runs are only classified as exceeding a limit if it is configured.
+    /// The default implementation returns no limits.
+    fn limits(&self) -> ResourceLimits {
+        ResourceLimits::default()
+    }
+
+    /// Prepares, builds and runs the tests and returns the parsed report.
+    fn run_tests(&self) -> io::Result<TestReport> {
+        self.prepare()?;
+        if let Some(report) = self.build()? {
+            return Ok(report);
+        }
+        let output = self.run()?;
+        let mut report = self.parse(&output);
+        add_exceeded_limit(&mut report, exceeded_limit(&output, &self.limits()));
+        Ok(report)
+    }
+}
+
+/// Adds the result of an exceeded resource limit, if any, to the given report of a failed run.
+/// Results that are more severe (e.g. build failures) are kept.
+pub(crate) fn add_exceeded_limit(report: &mut TestReport, exceeded: Option<TestResult>) {
+    if let (false, Some(result)) = (report.passed, exceeded) {
+        report.failure = Some(report.failure.map_or(result, |f| f.most_severe(result)));
+    }
+}
+
+/// Returns the result for a test run that exceeded a resource limit, if it did.
+///
+/// Only limits configured for the run are considered:
+/// With a CPU time limit, processes terminated with `SIGXCPU` are reported as timeouts.
+/// With a memory limit, processes killed with `SIGKILL` without having timed out
+/// are assumed to be killed by the OOM killer, as are processes reporting a failed allocation.
+/// Toolchains running the tests in a child process (e.g. `go test`) report these
+/// terminations in their output. Only lines written by the toolchains are recognized
+/// (see [`limit_message`]), so output of the tests or the tested code mentioning
+/// e.g. `MemoryError` doesn't change the result.
+/// Timeouts are already reported by the runners and not considered here.
+fn exceeded_limit(output: &RunOutput, limits: &ResourceLimits) -> Option<TestResult> {
+    if output.timed_out {
+        return None;
+    }
+    let configured = |result: TestResult| is_configured(result, limits);
+    #[cfg(unix)]
+    match output.signal() {
+        Some(libc::SIGXCPU) => return Some(TestResult::Timeout).filter(|&r| configured(r)),
+        Some(libc::SIGKILL) => {
+            return Some(TestResult::MemoryLimitExceeded).filter(|&r| configured(r))
+        }
+        _ => {}
+    }
+    [output.stdout_lossy(), output.stderr_lossy()]
+        .iter()
+        .find_map(|text| exceeded_limit_in_output(text, limits))
+}
+
+/// Returns the result for a test run that exceeded a resource limit
+/// according to the given output, like [`exceeded_limit`].
+/// Used where the termination of the test process doesn't belong to a single run,
+/// e.g. for the packages tested by [`GoBatchRunner`](crate::exam_tester::process::GoBatchRunner).
+pub(crate) fn exceeded_limit_in_output(text: &str, limits: &ResourceLimits) -> Option<TestResult> {
+    output_lines(text)
+        .iter()
+        .filter_map(|line| limit_message(line))
+        .find(|&result| is_configured(result, limits))
+}
+
+/// Checks whether the limit corresponding to the given result is configured.
+fn is_configured(result: TestResult, limits: &ResourceLimits) -> bool {
+    match result {
+        TestResult::Timeout => limits.cpu_seconds.is_some(),
+        TestResult::MemoryLimitExceeded => limits.memory_mb.is_some(),
+        _ => false,
+    }
+}
+
+/// Returns the lines of the given output of a test process.
+/// Output events of `go test -json` are replaced by the output they carry.
+fn output_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .flat_map(|line| {
+            let event_output = serde_json::from_str::<serde_json::Value>(line)
+                .ok()
+                .and_then(|event| Some(event.get("Output")?.as_str()?.to_string()));
+            match event_output {
+                Some(output) => output.lines().map(str::to_string).collect(),
+                None => vec![line.to_string()],
+            }
+        })
+        .collect()
+}
+
+/// Returns the result corresponding to a line written by one of the supported toolchains
+/// when a process exceeded a resource limit, if the line is such a message.
+fn limit_message(line: &str) -> Option<TestResult> {
+    let line = line.trim_end();
+    if line == "signal: CPU time limit exceeded" {
+        return Some(TestResult::Timeout);
+    }
+    let out_of_memory =
+        // go test, for a test binary that ran out of memory or was killed
+        line == "fatal error: runtime: out of memory"
+            || line == "signal: killed"
+            // Rust's default allocation error handler
+            || (line.starts_with("memory allocation of ") && line.ends_with(" bytes failed"))
+            // pytest, for a test failing with a MemoryError
+            || line
+                .strip_prefix("E ")
+                .map(str::trim_start)
+                .is_some_and(|error| error == "MemoryError" || error.starts_with("MemoryError: "))
+            // C++ runtime, for an uncaught std::bad_alloc
+            || line.starts_with("terminate called after throwing an instance of 'std::bad_alloc'")
+            // JVM, for an uncaught OutOfMemoryError
+            || (line.starts_with("Exception in thread \"")
+                && line.contains("\" java.lang.OutOfMemoryError"))
+            || line.starts_with("Terminating due to java.lang.OutOfMemoryError");
+    out_of_memory.then_some(TestResult::MemoryLimitExceeded)
+}
+
+impl TestReport {
+    /// Creates a report for a build that failed with the given output.
+    pub fn build_failure<S: Into<String>>(output: S) -> Self {
+        Self {
+            failure: Some(TestResult::BuildFailure),
+            output: output.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates the report of a build step from the output of the build command,
+    /// or `None` if the build succeeded.
+    /// A build that timed out is reported as [`TestResult::Timeout`].
+    pub fn from_build_output(output: &RunOutput) -> Option<Self> {
+        if !output.timed_out && output.status.success() {
+            return None;
+        }
+        let mut report = Self::build_failure(format!(
+            "{}{}",
+            output.stdout_lossy(),
+            output.stderr_lossy()
+        ));
+        if output.timed_out {
+            report.failure = Some(TestResult::Timeout);
+        }
+        Some(report)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::exam_tester::process::{CommandConfig, CommandRunner, ReportFormat};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::time::Duration;
+
+    fn output(raw_status: i32, stdout: &str) -> RunOutput {
+        RunOutput {
+            status: ExitStatus::from_raw(raw_status),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+            timed_out: false,
+            report: None,
+        }
+    }
+
+    fn limits() -> ResourceLimits {
+        ResourceLimits {
+            memory_mb: Some(256),
+            cpu_seconds: Some(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn killed_process() {
+        assert_eq!(
+            exceeded_limit(&output(libc::SIGKILL, ""), &limits()),
+            Some(TestResult::MemoryLimitExceeded)
+        );
+        assert_eq!(
+            exceeded_limit(&output(libc::SIGXCPU, ""), &limits()),
+            Some(TestResult::Timeout)
+        );
+
+        let mut timed_out = output(libc::SIGKILL, "");
+        timed_out.timed_out = true;
+        assert_eq!(exceeded_limit(&timed_out, &limits()), None);
+    }
+
+    /// Without limits, killed processes are not assumed to exceed one.
+    #[test]
+    fn no_limits() {
+        let no_limits = ResourceLimits::default();
+
+        assert_eq!(exceeded_limit(&output(libc::SIGKILL, ""), &no_limits), None);
+        assert_eq!(
+            exceeded_limit(
+                &output(1 << 8, "fatal error: runtime: out of memory\n"),
+                &no_limits
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn limit_messages() {
+        assert_eq!(
+            exceeded_limit(
+                &output(1 << 8, "fatal error: runtime: out of memory\n"),
+                &limits()
+            ),
+            Some(TestResult::MemoryLimitExceeded)
+        );
+        assert_eq!(
+            exceeded_limit(
+                &output(
+                    1 << 8,
+                    "{\"Action\":\"output\",\"Package\":\"task_1\",\"Output\":\"signal: killed\\n\"}\n"
+                ),
+                &limits()
+            ),
+            Some(TestResult::MemoryLimitExceeded)
+        );
+        assert_eq!(
+            exceeded_limit(&output(1 << 8, "E       MemoryError\n"), &limits()),
+            Some(TestResult::MemoryLimitExceeded)
+        );
+        assert_eq!(
+            exceeded_limit(&output(1 << 8, "--- FAIL: TestA\n"), &limits()),
+            None
+        );
+    }
+
+    #[test]
+    fn build_output() {
+        assert!(TestReport::from_build_output(&output(0, "")).is_none());
+
+        let failed = TestReport::from_build_output(&output(1 << 8, "error: x\n")).unwrap();
+        assert_eq!(failed.result(), TestResult::BuildFailure);
+        assert_eq!(failed.output, "error: x\n");
+
+        let mut timed_out = output(libc::SIGKILL, "");
+        timed_out.timed_out = true;
+        let timed_out = TestReport::from_build_output(&timed_out).unwrap();
+        assert_eq!(timed_out.result(), TestResult::Timeout);
+    }
+
+    /// Output of the tests mentioning memory errors doesn't count as exceeding the memory limit.
+    #[test]
+    fn printed_memory_error_is_test_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = CommandConfig::new(
+            "echo MemoryError; echo '>       with pytest.raises(MemoryError):'; exit 1",
+            ReportFormat::ExitCode,
+            "{task}.py",
+        );
+        let runner =
+            CommandRunner::new(dir.path(), Duration::from_secs(10), config).with_limits(limits());
+
+        let report = runner.run_tests().unwrap();
+
+        assert_eq!(report.result(), TestResult::TestFailure);
+    }
+}
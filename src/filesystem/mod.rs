@@ -1,13 +1,15 @@
+use crate::Error;
 use std::fs;
+use std::io;
 use std::path::Path;
 
 /// Gets the names of all subdirectories in the given directory.
-pub fn subdir_names(dir: &Path) -> Result<Vec<String>, String> {
+pub fn subdir_names(dir: &Path) -> Result<Vec<String>, Error> {
     let mut names = Vec::new();
-    let dir_iter = fs::read_dir(dir).map_err(|e| e.to_string())?;
+    let dir_iter = fs::read_dir(dir).map_err(|e| Error::io("read directory", dir, e))?;
 
     for entry in dir_iter {
-        let entry = entry.map_err(|e| e.to_string())?;
+        let entry = entry.map_err(|e| Error::io("read directory", dir, e))?;
         let path = entry.path();
         if path.is_dir() {
             names.push(entry.file_name().to_string_lossy().into_owned());
@@ -18,86 +20,104 @@ pub fn subdir_names(dir: &Path) -> Result<Vec<String>, String> {
 
 /// Recursively copies all contents of the source directory to the destination directory.
 /// Creates the destination directory if it does not exist.
-/// Does not overwrite existing files.
-pub fn copy_dir(src: &Path, dest: &Path) {
-    fs::create_dir_all(dest).unwrap();
-    let dir_iter = fs::read_dir(src).unwrap();
+/// Existing files with the same content are skipped. For existing files with a different content,
+/// `resolve` is called with the source and destination path and returns whether to overwrite the file.
+/// Symbolic links are not followed but result in an error, so files outside the source directory
+/// (e.g. of other students) are never copied.
+pub fn copy_dir(
+    src: &Path,
+    dest: &Path,
+    resolve: &mut dyn FnMut(&Path, &Path) -> bool,
+) -> Result<(), Error> {
+    fs::create_dir_all(dest).map_err(|e| Error::io("create directory", dest, e))?;
+    let dir_iter = fs::read_dir(src).map_err(|e| Error::io("read directory", src, e))?;
 
     for entry in dir_iter {
-        let entry = entry.unwrap();
+        let entry = entry.map_err(|e| Error::io("read directory", src, e))?;
         let src_path = entry.path();
         let dest_path = dest.join(entry.file_name());
+        let file_type = entry
+            .file_type()
+            .map_err(|e| Error::io("read directory", src, e))?;
 
-        if src_path.is_dir() {
-            copy_dir(&src_path, &dest_path);
-        } else if dest_path.exists() {
-            println!("File already exists: {:?}", dest_path);
+        if file_type.is_symlink() {
+            return Err(Error::io(
+                "copy",
+                &src_path,
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "symbolic links are not supported",
+                ),
+            ));
+        } else if file_type.is_dir() {
+            copy_dir(&src_path, &dest_path, resolve)?;
         } else {
-            fs::copy(&src_path, &dest_path).unwrap();
-        }
-    }
-}
-
-/// Copies all subdirectories of the source directory to the destination directory.
-/// Creates the destination directory if it does not exist.
-/// Each subdirectory is copied recursively including all files and subdirectories.
-/// Does not overwrite existing files.
-pub fn copy_subdirs(src: &Path, dest: &Path) {
-    let dir_iter = match fs::read_dir(src) {
-        Ok(iter) => iter,
-        Err(e) => {
-            eprintln!("Failed to read directory {:?}: {:?}", src, e);
-            return;
-        }
-    };
-
-    println!("Copying subdirectories:");
-    println!("  source: {:?}", src);
-    println!("  destination: {:?}", dest);
-    for entry in dir_iter {
-        let entry = entry.unwrap();
-        let src_path = entry.path();
-        let dest_path = dest.join(entry.file_name());
-
-        if src_path.is_dir() {
-            copy_dir(&src_path, &dest_path);
+            copy_file(&src_path, &dest_path, resolve)?;
         }
     }
+    Ok(())
 }
 
 /// Copies all files from the given list of filenames
 /// from the source directory to the destination directory.
-/// Creates the destination directory if it does not exist.
-/// Does not overwrite existing files.
-pub fn copy_files(files: &[String], src: &Path, dest: &Path) {
-    fs::create_dir_all(dest).unwrap();
+/// Filenames may be relative paths (e.g. `tests/grading.rs`).
+/// Creates the destination directory and any missing parent directories if they do not exist.
+/// Existing files are handled as by [`copy_dir`].
+pub fn copy_files(
+    files: &[String],
+    src: &Path,
+    dest: &Path,
+    resolve: &mut dyn FnMut(&Path, &Path) -> bool,
+) -> Result<(), Error> {
+    fs::create_dir_all(dest).map_err(|e| Error::io("create directory", dest, e))?;
 
     for file in files {
         let src_path = src.join(file);
         let dest_path = dest.join(file);
 
-        if dest_path.exists() {
-            println!("File already exists: {:?}", dest_path);
-        } else {
-            fs::copy(&src_path, &dest_path).unwrap();
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::io("create directory", parent, e))?;
         }
+        copy_file(&src_path, &dest_path, resolve)?;
     }
+    Ok(())
+}
+
+/// Copies a single file, see [`copy_dir`] for the handling of existing files.
+fn copy_file(
+    src: &Path,
+    dest: &Path,
+    resolve: &mut dyn FnMut(&Path, &Path) -> bool,
+) -> Result<(), Error> {
+    if dest.exists() && (same_content(src, dest)? || !resolve(src, dest)) {
+        return Ok(());
+    }
+    fs::copy(src, dest).map_err(|e| Error::io("copy", src, e))?;
+    Ok(())
+}
+
+/// Checks whether the given files have the same content.
+pub fn same_content(file: &Path, other: &Path) -> Result<bool, Error> {
+    let content = fs::read(file).map_err(|e| Error::io("read", file, e))?;
+    let other_content = fs::read(other).map_err(|e| Error::io("read", other, e))?;
+    Ok(content == other_content)
 }
 
 /// Appends the given content as a new line to the file with the given name in the given directory.
 /// Only appends the content if the file exists and doesn't already contain the line.
 /// Ensures that the new line is preceded with a newline and the file ends with a newline character.
 /// Doesn't create the file if it doesn't exist.
-pub fn append_to_file(file: &Path, content: &str) {
+#[allow(clippy::unnecessary_map_or)]
+pub fn append_to_file(file: &Path, content: &str) -> Result<(), Error> {
     if !file.exists() {
-        return;
+        return Ok(());
     }
 
     let content = content.trim_end();
-    let file_lines = fs::read_to_string(file).unwrap_or_default();
+    let file_lines = fs::read_to_string(file).map_err(|e| Error::io("read", file, e))?;
     let mut file_lines: Vec<&str> = file_lines.lines().collect();
     if file_lines.iter().any(|line| line == &content) {
-        return;
+        return Ok(());
     }
     if file_lines.last().map_or(true, |line| !line.is_empty()) {
         file_lines.push("");
@@ -106,18 +126,18 @@ pub fn append_to_file(file: &Path, content: &str) {
 
     let file_content = format!("{}\n", file_lines.join("\n"));
 
-    fs::write(file, file_content).unwrap();
+    fs::write(file, file_content).map_err(|e| Error::io("write", file, e))
 }
 
 /// Returns a list of files in the given directory that have the given suffix.
 /// Note that the suffix is just a string suffix, not a file extension.
 /// I.e. to catch a file extension, the dot must be included in the suffix.
-pub fn files_with_suffix(dir: &Path, suffix: &str) -> Result<Vec<String>, String> {
+pub fn files_with_suffix(dir: &Path, suffix: &str) -> Result<Vec<String>, Error> {
     let mut names = Vec::new();
-    let dir_iter = fs::read_dir(dir).map_err(|e| e.to_string())?;
+    let dir_iter = fs::read_dir(dir).map_err(|e| Error::io("read directory", dir, e))?;
 
     for entry in dir_iter {
-        let entry = entry.map_err(|e| e.to_string())?;
+        let entry = entry.map_err(|e| Error::io("read directory", dir, e))?;
         let path = entry.path();
         if path.is_file() {
             if let Some(file_name) = path.file_name() {
@@ -131,6 +151,67 @@ pub fn files_with_suffix(dir: &Path, suffix: &str) -> Result<Vec<String>, String
     Ok(names)
 }
 
+/// Returns a list of files in the given directory and all its subdirectories
+/// that have the given suffix.
+/// The returned names are relative to the given directory and sorted.
+pub fn files_with_suffix_recursive(dir: &Path, suffix: &str) -> Result<Vec<String>, Error> {
+    let mut names: Vec<String> = files_with_suffix(dir, suffix)?;
+    for subdir in subdir_names(dir)? {
+        for name in files_with_suffix_recursive(&dir.join(&subdir), suffix)? {
+            names.push(format!("{}/{}", subdir, name));
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Returns a list of files in the given directory matching the given pattern.
+/// The pattern may start with a fixed subdirectory path, e.g. `tests/*.rs`.
+/// The file name part may contain `*` wildcards matching any sequence of characters.
+/// The returned names are relative to the given directory and sorted.
+/// A missing subdirectory results in an empty list.
+pub fn files_matching(dir: &Path, pattern: &str) -> Result<Vec<String>, Error> {
+    let (subdir, file_pattern) = match pattern.rsplit_once('/') {
+        Some((subdir, file_pattern)) => (Some(subdir), file_pattern),
+        None => (None, pattern),
+    };
+    let search_dir = match subdir {
+        Some(subdir) => dir.join(subdir),
+        None => dir.to_path_buf(),
+    };
+    if subdir.is_some() && !search_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = files_with_suffix(&search_dir, "")?
+        .into_iter()
+        .filter(|name| matches_wildcard(file_pattern, name))
+        .map(|name| match subdir {
+            Some(subdir) => format!("{}/{}", subdir, name),
+            None => name,
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Checks whether the given name matches the pattern,
+/// where `*` matches any (possibly empty) sequence of characters.
+pub fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            name.char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(name.len()))
+                .any(|i| matches_wildcard(rest, &name[i..]))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,4 +240,54 @@ mod tests {
         assert!(names.contains(&"subdir2".to_string()));
         assert!(names.contains(&"subdir3".to_string()));
     }
+
+    #[test]
+    fn files_with_suffix_recursive_nested() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir_path = temp_dir.path();
+
+        fs::create_dir_all(temp_dir_path.join("pkg/sub")).unwrap();
+        File::create(temp_dir_path.join("Task.java")).unwrap();
+        File::create(temp_dir_path.join("pkg/sub/Util.java")).unwrap();
+        File::create(temp_dir_path.join("pkg/notes.txt")).unwrap();
+
+        let names = files_with_suffix_recursive(temp_dir_path, ".java").unwrap();
+        assert_eq!(
+            names,
+            vec!["Task.java".to_string(), "pkg/sub/Util.java".to_string()]
+        );
+    }
+
+    #[test]
+    fn matches_wildcard_patterns() {
+        assert!(matches_wildcard("*_test.go", "task_1_test.go"));
+        assert!(matches_wildcard(
+            "*_grading_test.go",
+            "task_1_grading_test.go"
+        ));
+        assert!(!matches_wildcard("*_grading_test.go", "task_1_test.go"));
+        assert!(matches_wildcard("test_*.py", "test_task.py"));
+        assert!(matches_wildcard("*", ""));
+        assert!(!matches_wildcard("task.go", "task_1.go"));
+    }
+
+    #[test]
+    fn files_matching_subdir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let temp_dir_path = temp_dir.path();
+
+        fs::create_dir(temp_dir_path.join("tests")).unwrap();
+        File::create(temp_dir_path.join("tests/grading.rs")).unwrap();
+        File::create(temp_dir_path.join("tests/notes.txt")).unwrap();
+        File::create(temp_dir_path.join("lib.rs")).unwrap();
+
+        let names = files_matching(temp_dir_path, "tests/*.rs").unwrap();
+        assert_eq!(names, vec!["tests/grading.rs".to_string()]);
+
+        let names = files_matching(temp_dir_path, "*.rs").unwrap();
+        assert_eq!(names, vec!["lib.rs".to_string()]);
+
+        let names = files_matching(temp_dir_path, "missing/*.rs").unwrap();
+        assert!(names.is_empty());
+    }
 }
@@ -1,3 +1,6 @@
 pub mod exam_tester;
 
+mod error;
 pub(crate) mod filesystem;
+
+pub use error::Error;